@@ -0,0 +1,86 @@
+//! Benchmark of the local image checks.
+//!
+//! Runs on synthetic headers, plus the images of the directory pointed by
+//! `CBZLINT_BENCH_IMAGES` (if set) to get representative numbers.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use std::{env, fs};
+
+/// Build a JPEG header of `width`x`height`, with an EXIF segment if
+/// `with_exif` is set.
+fn jpeg(width: u16, height: u16, with_exif: bool) -> Vec<u8> {
+    let mut bytes = vec![0xFF, 0xD8];
+    // JFIF header, 72 DPI.
+    bytes.extend_from_slice(&[0xFF, 0xE0, 0x00, 0x10]);
+    bytes.extend_from_slice(b"JFIF\0\x01\x01\x01\x00\x48\x00\x48\x00\x00");
+    if with_exif {
+        // Big-endian TIFF with a single entry: Orientation = 1.
+        bytes.extend_from_slice(&[0xFF, 0xE1, 0x00, 0x22]);
+        bytes.extend_from_slice(b"Exif\0\0MM\0\x2A\0\0\0\x08");
+        bytes.extend_from_slice(&[0x00, 0x01, 0x01, 0x12, 0x00, 0x03, 0x00, 0x00, 0x00, 0x01]);
+        bytes.extend_from_slice(&[0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+    }
+    // Baseline Start Of Frame, 3 components.
+    bytes.extend_from_slice(&[0xFF, 0xC0, 0x00, 0x11, 0x08]);
+    bytes.extend_from_slice(&height.to_be_bytes());
+    bytes.extend_from_slice(&width.to_be_bytes());
+    bytes.extend_from_slice(&[0x03, 0x01, 0x22, 0x00, 0x02, 0x11, 0x01, 0x03, 0x11, 0x01]);
+    // Start Of Scan, without data, then End Of Image.
+    bytes.extend_from_slice(&[
+        0xFF, 0xDA, 0x00, 0x0C, 0x03, 0x01, 0x00, 0x02, 0x11, 0x03, 0x11,
+    ]);
+    bytes.extend_from_slice(&[0x00, 0x3F, 0x00, 0xFF, 0xD9]);
+
+    bytes
+}
+
+/// Build a PNG header of `width`x`height`.
+fn png(width: u32, height: u32) -> Vec<u8> {
+    let mut bytes = b"\x89PNG\r\n\x1A\n".to_vec();
+    bytes.extend_from_slice(&13_u32.to_be_bytes());
+    bytes.extend_from_slice(b"IHDR");
+    bytes.extend_from_slice(&width.to_be_bytes());
+    bytes.extend_from_slice(&height.to_be_bytes());
+    // 8-bit RGB, then a dummy CRC.
+    bytes.extend_from_slice(&[0x08, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+    bytes.extend_from_slice(&0_u32.to_be_bytes());
+    bytes.extend_from_slice(b"IEND\xAE\x42\x60\x82");
+
+    bytes
+}
+
+fn bench_synthetic(c: &mut Criterion) {
+    let images = [
+        ("jpeg", jpeg(1920, 2560, false)),
+        ("jpeg_exif", jpeg(1920, 2560, true)),
+        ("png", png(1920, 2560)),
+    ];
+
+    for (name, bytes) in &images {
+        c.bench_function(&format!("inspect_{name}"), |b| {
+            b.iter(|| cbzlint::picture::inspect(black_box(bytes)))
+        });
+    }
+}
+
+fn bench_directory(c: &mut Criterion) {
+    let dir = match env::var_os("CBZLINT_BENCH_IMAGES") {
+        Some(dir) => dir,
+        None => return,
+    };
+    let images = fs::read_dir(dir)
+        .expect("readable bench directory")
+        .filter_map(|entry| fs::read(entry.ok()?.path()).ok())
+        .collect::<Vec<_>>();
+
+    c.bench_function("inspect_directory", |b| {
+        b.iter(|| {
+            for bytes in &images {
+                let _ = black_box(cbzlint::picture::inspect(black_box(bytes)));
+            }
+        })
+    });
+}
+
+criterion_group!(benches, bench_synthetic, bench_directory);
+criterion_main!(benches);
@@ -0,0 +1,270 @@
+//! Archive formats of the books: ZIP (CBZ) and tar (CBT), or none for the
+//! unpacked books (a directory of images).
+
+use anyhow::{Context, Error, Result};
+use std::{
+    convert::TryFrom,
+    ffi::OsStr,
+    fmt, fs, io,
+    io::{Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+};
+use zip::{result::ZipError, DateTime, ZipArchive};
+
+/// Extensions of the books, one per supported archive format.
+pub(crate) const BOOK_EXTENSIONS: &[&str] = &["cbz", "cbt"];
+
+/// A seekable reader, to read an archive from.
+pub(crate) trait ReadSeek: Read + Seek {}
+
+impl<T: Read + Seek> ReadSeek for T {}
+
+/// The archive is damaged beyond reading, with the details.
+#[derive(Debug)]
+pub(crate) struct Corrupt(pub(crate) String);
+
+impl fmt::Display for Corrupt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "corrupt archive: {}", self.0)
+    }
+}
+
+impl std::error::Error for Corrupt {}
+
+/// An entry of an archive.
+pub(crate) struct Entry<'a> {
+    /// Path of the entry, inside the archive.
+    pub(crate) name: String,
+    /// Whether the entry is a regular file (e.g. not a directory).
+    pub(crate) is_file: bool,
+    /// Modified date of the entry, unless unpacked.
+    pub(crate) modified: Option<DateTime>,
+    /// Content of the entry.
+    pub(crate) reader: Box<dyn Read + 'a>,
+}
+
+/// An archive, whose entries are accessed by index.
+pub(crate) trait Archive {
+    /// Return the number of entries.
+    fn len(&self) -> usize;
+
+    /// Return the entry at `index`.
+    ///
+    /// Fails with `Corrupt` if the entry is damaged.
+    fn entry(&mut self, index: usize) -> Result<Entry<'_>>;
+}
+
+impl<R: Read + Seek> Archive for ZipArchive<R> {
+    fn len(&self) -> usize {
+        ZipArchive::len(self)
+    }
+
+    fn entry(&mut self, index: usize) -> Result<Entry<'_>> {
+        let entry = self.by_index(index).map_err(|err| match err {
+            // The central directory disagrees with the local headers.
+            ZipError::InvalidArchive(msg) => {
+                let detail = format!("entry #{}: {}", index + 1, msg.to_lowercase());
+                Error::new(Corrupt(detail))
+            }
+            err => Error::new(err).context("failed to read ZIP entry"),
+        })?;
+
+        Ok(Entry {
+            name: entry.name().to_owned(),
+            is_file: entry.is_file(),
+            modified: Some(entry.last_modified()),
+            reader: Box::new(entry),
+        })
+    }
+}
+
+/// A tar archive, indexed when opened.
+struct TarArchive<R> {
+    reader: R,
+    entries: Vec<TarEntry>,
+}
+
+/// Location and metadata of an entry of a tar archive.
+struct TarEntry {
+    name: String,
+    is_file: bool,
+    modified: DateTime,
+    /// Offset of the content.
+    position: u64,
+    size: u64,
+}
+
+impl<R: Read + Seek> TarArchive<R> {
+    /// Index the entries of the tar archive read from `reader`.
+    fn new(reader: R) -> Result<Self> {
+        let mut archive = tar::Archive::new(reader);
+        let entries = archive
+            .entries_with_seek()
+            .and_then(|entries| {
+                entries
+                    .map(|entry| {
+                        let entry = entry?;
+                        Ok(TarEntry {
+                            name: entry.path()?.to_string_lossy().into_owned(),
+                            is_file: entry.header().entry_type().is_file(),
+                            // Dates a ZIP can't represent (before 1980) are
+                            // wrong anyway.
+                            modified: to_date_time(entry.header().mtime()?).unwrap_or_default(),
+                            position: entry.raw_file_position(),
+                            size: entry.size(),
+                        })
+                    })
+                    .collect::<io::Result<Vec<_>>>()
+            })
+            // Headers are only read here, so any error means a damaged index.
+            .map_err(|err| Corrupt(err.to_string().to_lowercase()))?;
+
+        Ok(Self {
+            reader: archive.into_inner(),
+            entries,
+        })
+    }
+}
+
+impl<R: Read + Seek> Archive for TarArchive<R> {
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn entry(&mut self, index: usize) -> Result<Entry<'_>> {
+        let entry = &self.entries[index];
+        self.reader
+            .seek(SeekFrom::Start(entry.position))
+            .context("failed to read tar entry")?;
+
+        Ok(Entry {
+            name: entry.name.clone(),
+            is_file: entry.is_file,
+            modified: Some(entry.modified),
+            reader: Box::new((&mut self.reader).take(entry.size)),
+        })
+    }
+}
+
+/// An unpacked book: a directory of images, including its sub-directories.
+struct DirArchive {
+    /// Files, by path relative to the directory.
+    files: Vec<(String, PathBuf)>,
+}
+
+impl Archive for DirArchive {
+    fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    fn entry(&mut self, index: usize) -> Result<Entry<'_>> {
+        let (name, path) = &self.files[index];
+        let file = fs::File::open(path).with_context(|| format!("cannot open {name}"))?;
+
+        Ok(Entry {
+            name: name.clone(),
+            is_file: true,
+            // Set when packing, nothing to check yet.
+            modified: None,
+            reader: Box::new(file),
+        })
+    }
+}
+
+/// Open the unpacked book at `dir`, and return it with its size.
+pub(crate) fn open_dir(dir: &Path) -> Result<(Box<dyn Archive>, u64)> {
+    let mut files = Vec::new();
+    let mut size = 0;
+    let mut dirs = vec![dir.to_owned()];
+
+    while let Some(current) = dirs.pop() {
+        let entries = fs::read_dir(&current)
+            .with_context(|| format!("failed to read dir {}", current.display()))?;
+        for entry in entries {
+            let entry = entry
+                .with_context(|| format!("cannot access entry under {}", current.display()))?;
+            let path = entry.path();
+            let metadata = entry
+                .metadata()
+                .with_context(|| format!("cannot access {}", path.display()))?;
+            if metadata.is_dir() {
+                dirs.push(path);
+                continue;
+            }
+            // Named as in an archive, whatever the platform.
+            let name = path
+                .strip_prefix(dir)
+                .unwrap_or(&path)
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            size += metadata.len();
+            files.push((name, path));
+        }
+    }
+    // Listed in the order they would be packed.
+    files.sort_unstable();
+
+    Ok((Box::new(DirArchive { files }), size))
+}
+
+/// Open the archive of the book at `path`, read from `reader`, according to
+/// the book's extension.
+///
+/// Fails with `Corrupt` if the archive's index is damaged.
+pub(crate) fn open(path: &Path, reader: Box<dyn ReadSeek>) -> Result<Box<dyn Archive>> {
+    if path.extension() == Some(OsStr::new("cbt")) {
+        return Ok(Box::new(TarArchive::new(reader)?));
+    }
+
+    match ZipArchive::new(reader) {
+        Ok(archive) => Ok(Box::new(archive)),
+        // The central directory itself is damaged.
+        Err(ZipError::InvalidArchive(msg)) => Err(Corrupt(msg.to_lowercase()).into()),
+        Err(err) => Err(err).context("read error"),
+    }
+}
+
+/// Return the details of the corruption if `err` is a `Corrupt`, otherwise
+/// `err` itself.
+pub(crate) fn corruption(err: Error) -> Result<String> {
+    err.downcast::<Corrupt>().map(|Corrupt(detail)| detail)
+}
+
+/// Return true if `path` has the extension of a book.
+pub(crate) fn is_book(path: &Path) -> bool {
+    path.extension()
+        .and_then(OsStr::to_str)
+        .is_some_and(|extension| BOOK_EXTENSIONS.contains(&extension))
+}
+
+/// Convert a Unix timestamp to a ZIP date, in UTC, if representable.
+fn to_date_time(timestamp: u64) -> Option<DateTime> {
+    // Days to civil date, see http://howardhinnant.github.io/date_algorithms.html
+    let days = timestamp / 86_400 + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = era * 400 + year_of_era + u64::from(month <= 2);
+    let seconds = timestamp % 86_400;
+
+    DateTime::from_date_and_time(
+        u16::try_from(year).ok()?,
+        u8::try_from(month).ok()?,
+        u8::try_from(day).ok()?,
+        u8::try_from(seconds / 3600).ok()?,
+        u8::try_from(seconds / 60 % 60).ok()?,
+        u8::try_from(seconds % 60).ok()?,
+    )
+    .ok()
+}
@@ -0,0 +1,223 @@
+//! Authors names comparison.
+
+use std::collections::{BTreeSet, HashMap};
+
+/// Authors aliases, to reconcile spellings the normalization can't.
+///
+/// Aliases are bidirectional: both spellings map to the same canonical form.
+#[derive(Debug, Default)]
+pub(crate) struct Aliases(HashMap<String, String>);
+
+impl Aliases {
+    /// Initialize the aliases from a `name => alias` mapping.
+    pub(crate) fn new(aliases: &HashMap<String, String>) -> Self {
+        Self(
+            aliases
+                .iter()
+                .map(|(name, alias)| (normalize_name(alias), normalize_name(name)))
+                .collect(),
+        )
+    }
+
+    /// Return the canonical form of a normalized name.
+    fn canonical(&self, name: String) -> String {
+        self.0.get(&name).cloned().unwrap_or(name)
+    }
+
+    /// Whether a normalized name has an alias, or is one.
+    fn contains(&self, name: &str) -> bool {
+        self.0.contains_key(name) || self.0.values().any(|canonical| canonical == name)
+    }
+}
+
+/// Check if two normalized authors lists have the same names, in any order.
+pub(crate) fn same_names(a: &[String], b: &[String]) -> bool {
+    let mut a = a.iter().collect::<Vec<_>>();
+    let mut b = b.iter().collect::<Vec<_>>();
+    a.sort_unstable();
+    b.sort_unstable();
+
+    a == b
+}
+
+/// Compute the similarity (from 0 to 100) of two authors lists, as a token set
+/// ratio: the order of the names, and names missing from one list (e.g. a
+/// middle name), don't lower the score.
+pub(crate) fn similarity(a: &str, b: &str, known: &[String], aliases: &Aliases) -> f64 {
+    let a = tokens(a, known, aliases);
+    let b = tokens(b, known, aliases);
+    let common = a.intersection(&b).cloned().collect::<Vec<_>>().join(" ");
+    let only_a = a.difference(&b).cloned().collect::<Vec<_>>().join(" ");
+    let only_b = b.difference(&a).cloned().collect::<Vec<_>>().join(" ");
+    let with_a = format!("{common} {only_a}").trim().to_owned();
+    let with_b = format!("{common} {only_b}").trim().to_owned();
+
+    [
+        ratio(&common, &with_a),
+        ratio(&common, &with_b),
+        ratio(&with_a, &with_b),
+    ]
+    .iter()
+    .copied()
+    .fold(0.0, f64::max)
+}
+
+/// Return the set of (normalized) words of an authors list.
+fn tokens(authors: &str, known: &[String], aliases: &Aliases) -> BTreeSet<String> {
+    normalize(authors, known, aliases)
+        .into_iter()
+        .flat_map(|name| {
+            name.split(|c: char| c.is_whitespace() || c == ',' || c == '.')
+                .filter(|word| !word.is_empty())
+                .map(ToOwned::to_owned)
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Normalize authors list for easier comparison, best effort...
+///
+/// Names are separated by `-`, but some contain hyphens too (e.g.
+/// `Saint-Ogan`): consecutive parts forming one of the `known` names (e.g. the
+/// ones listed on bedetheque), or an aliased one, are kept together.
+pub(crate) fn normalize(authors: &str, known: &[String], aliases: &Aliases) -> Vec<String> {
+    let parts = authors.split('-').map(normalize_name).collect::<Vec<_>>();
+    let known = known
+        .iter()
+        .map(|name| normalize_name(name))
+        .collect::<BTreeSet<_>>();
+    let mut names = Vec::new();
+    let mut start = 0;
+
+    while start < parts.len() {
+        // Longest hyphenated name first, a single part otherwise.
+        let end = (start + 2..=parts.len())
+            .rev()
+            .find(|&end| {
+                let name = parts[start..end].join("-");
+                known.contains(&name) || aliases.contains(&name)
+            })
+            .unwrap_or(start + 1);
+        names.push(aliases.canonical(parts[start..end].join("-")));
+        start = end;
+    }
+
+    names
+}
+
+/// Compute the similarity (from 0 to 100) of two strings, from their edit
+/// distance (insertions and deletions only).
+fn ratio(a: &str, b: &str) -> f64 {
+    let a = a.chars().collect::<Vec<_>>();
+    let b = b.chars().collect::<Vec<_>>();
+    let total = a.len() + b.len();
+    if total == 0 {
+        return 100.0;
+    }
+
+    // Classic dynamic programming, one row at a time.
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+    for (i, ca) in a.iter().enumerate() {
+        let mut prev = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let current = row[j + 1];
+            row[j + 1] = if ca == cb {
+                prev
+            } else {
+                row[j].min(current) + 1
+            };
+            prev = current;
+        }
+    }
+
+    #[allow(clippy::cast_precision_loss)] // Names are way below 2^52 chars.
+    let score = (total - row[b.len()]) as f64 * 100.0 / total as f64;
+    score
+}
+
+/// Normalize an author's name.
+fn normalize_name(name: &str) -> String {
+    name.trim()
+        // Case insensitive.
+        .to_lowercase()
+        // Romanization mismatch.
+        .replace(['ā', 'â'], "aa")
+        .replace(['ū', 'û'], "uu")
+        .replace(['ē', 'ê'], "ee")
+        .replace(['ō', 'ô'], "ou")
+        .replace("oo", "ou")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|&name| name.to_owned()).collect()
+    }
+
+    #[test]
+    fn normalize_hyphenated_unknown() {
+        let aliases = Aliases::default();
+
+        assert_eq!(
+            normalize("Saint-Ogan-Hergé", &[], &aliases),
+            names(&["saint", "ogan", "hergé"])
+        );
+        assert_eq!(
+            normalize("Mézières Jean-Claude-Christin Pierre", &[], &aliases),
+            names(&["mézières jean", "claude", "christin pierre"])
+        );
+    }
+
+    #[test]
+    fn normalize_hyphenated_known() {
+        let aliases = Aliases::default();
+        let known = names(&["Saint-Ogan", "Hergé"]);
+
+        assert_eq!(
+            normalize("Saint-Ogan-Hergé", &known, &aliases),
+            names(&["saint-ogan", "hergé"])
+        );
+        let known = names(&["Mézières Jean-Claude", "Christin Pierre"]);
+        assert_eq!(
+            normalize("Mézières Jean-Claude-Christin Pierre", &known, &aliases),
+            names(&["mézières jean-claude", "christin pierre"])
+        );
+    }
+
+    #[test]
+    fn normalize_hyphenated_alias() {
+        let aliases = Aliases::new(&HashMap::from([(
+            "Saint-Ogan".to_owned(),
+            "St-Ogan".to_owned(),
+        )]));
+
+        assert_eq!(
+            normalize("St-Ogan-Hergé", &[], &aliases),
+            names(&["saint-ogan", "hergé"])
+        );
+    }
+
+    #[test]
+    fn same_names_hyphenated() {
+        let aliases = Aliases::default();
+        let known = names(&["Mézières Jean-Claude", "Christin Pierre"]);
+        let expected = normalize("Christin Pierre-Mézières Jean-Claude", &known, &aliases);
+
+        assert!(same_names(
+            &expected,
+            &normalize("Mézières Jean-Claude-Christin Pierre", &known, &aliases)
+        ));
+        assert!(!same_names(
+            &expected,
+            &normalize("Mézières Jean-Christin Pierre", &known, &aliases)
+        ));
+        // Without the known names, the parts still compare the same.
+        assert!(same_names(
+            &normalize("Saint-Ogan-Hergé", &[], &aliases),
+            &normalize("Hergé-Saint-Ogan", &[], &aliases)
+        ));
+    }
+}
@@ -1,10 +1,26 @@
 //! HTTP client to retrieve information from bedetheque.
 
-use crate::metadata::VolumeInfo;
-use anyhow::{anyhow, Context, Result};
+use crate::{
+    cache::Lookups,
+    metadata::{AuthorLabels, VolumeInfo},
+    provider::{Lookup, MetadataProvider},
+    series::SeriesConfig,
+    tape::{self, Tape},
+    warning::Warning,
+};
+use anyhow::{anyhow, bail, Context, Error, Result};
 use kuchiki::traits::*;
 use once_cell::sync::Lazy;
-use std::{cell::RefCell, collections::HashMap, thread, time::Duration};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::{
+    cell::{Cell, RefCell},
+    collections::{BTreeSet, HashMap},
+    fmt,
+    str::FromStr,
+    thread,
+    time::{Duration, Instant},
+};
 use url::Url;
 
 /// Bedetheque homepage.
@@ -31,75 +47,496 @@ static TITLE_SELECTOR: Lazy<kuchiki::Selectors> =
 static VOLUME_SELECTOR: Lazy<kuchiki::Selectors> =
     Lazy::new(|| kuchiki::Selectors::compile(".num").expect("invalid volume selector"));
 
+/// CSS selector to extract the album links from a series page.
+static ALBUMS_SELECTOR: Lazy<kuchiki::Selectors> = Lazy::new(|| {
+    kuchiki::Selectors::compile(".album-main a.titre").expect("invalid albums selector")
+});
+
+/// Regex to extract the volume number from an album title.
+///
+/// Album titles look like `Naruto -1- Uzumaki Naruto`.
+static ALBUM_NUMBER_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"-(?P<volume>[0-9]+)-").expect("valid album number regexp"));
+
+/// Punctuation ignored when matching titles, known to cause lookup issues.
+const STRIPPED_CHARS: [char; 3] = ['!', '?', ':'];
+
+/// Unicode dashes, matched as a plain hyphen (e.g. `Astérix – Le Domaine` and
+/// `Astérix - Le Domaine`).
+const DASHES: [char; 7] = [
+    '\u{2010}', // Hyphen.
+    '\u{2011}', // Non-breaking hyphen.
+    '\u{2012}', // Figure dash.
+    '\u{2013}', // En dash.
+    '\u{2014}', // Em dash.
+    '\u{2015}', // Horizontal bar.
+    '\u{2212}', // Minus sign.
+];
+
+/// Leading articles, that bedetheque moves at the end of series titles (e.g.
+/// `Aventures de Tintin, Les`).
+const ARTICLES: [&str; 5] = ["le", "la", "les", "l'", "l’"];
+
+/// How long the metadata of a book are kept in cache.
+const INFO_CACHE_TTL: Duration = Duration::from_hours(1);
+
 /// A volume identifier, used as cache key.
-#[derive(Debug, Eq, Hash, PartialEq)]
-struct Volume {
+#[derive(Debug, Clone, Eq, Hash, PartialEq, Serialize, Deserialize)]
+#[allow(clippy::struct_field_names)] // The volume number of a volume.
+pub(crate) struct Volume {
     title: String,
     // Optional because One-Shot don't have one.
     volume: Option<u8>,
+    // Unknown when looked up from the series page.
+    language: Option<Language>,
+}
+
+/// Language of an edition, to search on bedetheque.
+#[derive(Debug, Clone, Copy, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub(crate) enum Language {
+    French,
+    English,
+    German,
+    Spanish,
+    Italian,
+    Dutch,
+}
+
+impl Language {
+    /// Return the language code (e.g. `fr`).
+    pub(crate) fn code(self) -> &'static str {
+        match self {
+            Self::French => "fr",
+            Self::English => "en",
+            Self::German => "de",
+            Self::Spanish => "es",
+            Self::Italian => "it",
+            Self::Dutch => "nl",
+        }
+    }
+
+    /// Return the value of the search form's language field.
+    fn search_value(self) -> &'static str {
+        match self {
+            Self::French => "Français",
+            Self::English => "Anglais",
+            Self::German => "Allemand",
+            Self::Spanish => "Espagnol",
+            Self::Italian => "Italien",
+            Self::Dutch => "Néerlandais",
+        }
+    }
+}
+
+impl FromStr for Language {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "fr" => Self::French,
+            "en" => Self::English,
+            "de" => Self::German,
+            "es" => Self::Spanish,
+            "it" => Self::Italian,
+            "nl" => Self::Dutch,
+            _ => bail!("expected one of `fr`, `en`, `de`, `es`, `it` or `nl`"),
+        })
+    }
+}
+
+/// Field of the search form used to look up the books.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub(crate) enum SearchField {
+    /// Series name (works for most books).
+    #[default]
+    Series,
+    /// Album title (works better for some one-shots).
+    Title,
+}
+
+impl SearchField {
+    /// Return the other field, to fall back on.
+    fn alternate(self) -> Self {
+        match self {
+            Self::Series => Self::Title,
+            Self::Title => Self::Series,
+        }
+    }
+
+    /// Return the name of the search form's field.
+    fn param(self) -> &'static str {
+        match self {
+            Self::Series => "RechSerie",
+            Self::Title => "RechTitre",
+        }
+    }
+}
+
+impl FromStr for SearchField {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "series" => Self::Series,
+            "title" => Self::Title,
+            _ => bail!("expected `series` or `title`"),
+        })
+    }
+}
+
+/// Network usage statistics.
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct Stats {
+    /// Number of HTTP requests sent.
+    pub(crate) requests: u64,
+    /// Number of bytes received (approximate, decompressed HTML).
+    pub(crate) bytes: u64,
+}
+
+/// Error returned once the maximum number of requests is reached.
+#[derive(Debug)]
+pub(crate) struct BudgetExhausted;
+
+impl fmt::Display for BudgetExhausted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "request budget exhausted")
+    }
+}
+
+impl std::error::Error for BudgetExhausted {}
+
+/// Maximum length of the response body reported in an HTTP error.
+const SNIPPET_LEN: usize = 120;
+
+/// Error returned when bedetheque answers with an error status.
+#[derive(Debug)]
+pub(crate) struct HttpStatus {
+    /// Status code (e.g. 429).
+    code: u16,
+    /// Status text (e.g. `Too Many Requests`).
+    text: String,
+    /// Beginning of the response body, whitespaces collapsed.
+    snippet: String,
+}
+
+impl HttpStatus {
+    /// Extract the status and the beginning of the body of `response`.
+    fn new(code: u16, response: ureq::Response) -> Self {
+        let text = response.status_text().to_owned();
+        let body = response.into_string().unwrap_or_default();
+        let snippet = body
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .chars()
+            .take(SNIPPET_LEN)
+            .collect();
+
+        Self {
+            code,
+            text,
+            snippet,
+        }
+    }
+}
+
+impl fmt::Display for HttpStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "HTTP {} {}", self.code, self.text)?;
+        if !self.snippet.is_empty() {
+            write!(f, ": {}", self.snippet)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for HttpStatus {}
+
+/// Check if `err` was caused by the network (transport error or error status).
+pub(crate) fn is_network_error(err: &Error) -> bool {
+    err.chain().any(|cause| {
+        cause.is::<ureq::Error>() || cause.is::<ureq::Transport>() || cause.is::<HttpStatus>()
+    })
+}
+
+/// Check if `err` was caused by the exhaustion of the request budget.
+pub(crate) fn is_budget_exhausted(err: &Error) -> bool {
+    err.chain()
+        .any(<dyn std::error::Error>::is::<BudgetExhausted>)
 }
 
 /// A bedetheque client.
 pub(crate) struct Client {
     agent: ureq::Agent,
+    stats: Cell<Stats>,
     cache: RefCell<HashMap<Volume, Url>>,
+    /// Metadata of the books, with the time they were retrieved.
+    info_cache: RefCell<HashMap<Url, (Instant, VolumeInfo)>>,
+    /// Where to record (or replay) the responses, if any.
+    tape: Option<Tape>,
+    /// Languages to search, in order.
+    languages: Vec<Language>,
+    /// Search field tried first.
+    search_field: SearchField,
+    /// Reuse the previous lookups, instead of searching every book.
+    reuse_cache: bool,
+    /// Language in which each book was found.
+    found_in: RefCell<HashMap<Url, Language>>,
+    /// Minor issues met while locating each book.
+    warnings: RefCell<HashMap<Url, Vec<Warning>>>,
+    /// Maximum number of requests to send, if any.
+    max_requests: Option<u64>,
+    /// Labels of the authors fields on the album pages.
+    author_labels: AuthorLabels,
 }
 
 impl Client {
     /// Initialize a new Bedetheque client.
-    pub(crate) fn new() -> Self {
+    ///
+    /// Books are searched in each of `languages`, in order, using
+    /// `search_field` first then the other one. With a tape, the responses are
+    /// either recorded or replayed (without network access).
+    ///
+    /// Once `max_requests` requests are sent, every new request fails with
+    /// [`BudgetExhausted`].
+    ///
+    /// Unless `reuse_cache` is set, every book is searched even if a previous
+    /// lookup already found it.
+    ///
+    /// The authors are read from the album pages' fields named by
+    /// `author_labels`.
+    pub(crate) fn new(
+        tape: Option<Tape>,
+        languages: Vec<Language>,
+        search_field: SearchField,
+        max_requests: Option<u64>,
+        reuse_cache: bool,
+        author_labels: AuthorLabels,
+    ) -> Self {
         Self {
             agent: ureq::Agent::new(),
+            stats: Cell::new(Stats::default()),
             cache: RefCell::new(HashMap::new()),
+            info_cache: RefCell::new(HashMap::new()),
+            tape,
+            languages,
+            search_field,
+            reuse_cache,
+            found_in: RefCell::new(HashMap::new()),
+            warnings: RefCell::new(HashMap::new()),
+            max_requests,
+            author_labels,
+        }
+    }
+
+    /// Add previous lookups (e.g. from a persistent cache) to the cache.
+    pub(crate) fn preload(&self, lookups: Lookups) {
+        self.cache.borrow_mut().extend(lookups);
+    }
+
+    /// Return the URL found by a previous lookup of `key`, if reused.
+    fn cached(&self, key: &Volume) -> Option<Url> {
+        if !self.reuse_cache {
+            return None;
         }
+
+        self.cache.borrow().get(key).cloned()
+    }
+
+    /// Return every lookup in cache.
+    pub(crate) fn lookups(&self) -> Lookups {
+        self.cache
+            .borrow()
+            .iter()
+            .map(|(volume, url)| (volume.clone(), url.clone()))
+            .collect()
+    }
+
+    /// Return the HTTP agent, to share its connections.
+    pub(crate) fn agent(&self) -> &ureq::Agent {
+        &self.agent
+    }
+
+    /// Return the network usage statistics so far.
+    pub(crate) fn stats(&self) -> Stats {
+        self.stats.get()
     }
 
     /// Find the book's URL on bedetheque.
+    ///
+    /// Each language is tried in order, the first match wins.
     pub(crate) fn find_book(&self, title: &str, volume: Option<u8>) -> Result<Url> {
-        let key = Volume {
-            title: title.to_owned(),
-            volume,
-        };
-
-        if let Some(url) = self.cache.borrow().get(&key) {
-            return Ok(url.clone());
+        let punctuation = title
+            .contains(STRIPPED_CHARS)
+            .then(|| Warning::Punctuation(title.to_owned(), STRIPPED_CHARS.iter().collect()));
+
+        let mut csrf_token = None;
+        let mut res = Err(anyhow!("no language to search"));
+
+        for &language in &self.languages {
+            res = self.find_book_in_language(title, volume, language, &mut csrf_token);
+            if let Ok(url) = &res {
+                self.found_in.borrow_mut().insert(url.clone(), language);
+                break;
+            }
         }
 
-        let csrf_token = self.get_csrf_token()?;
-        let mut url = SEARCH_URL.clone();
-        url.query_pairs_mut()
-            .append_pair("csrf_token_bel", &csrf_token)
-            .append_pair("RechSerie", &normalize(title))
-            .append_pair("RechLangue", "Français");
+        if let Some(warning) = punctuation {
+            res = match res {
+                Ok(url) => {
+                    self.warn(&url, warning);
+                    Ok(url)
+                }
+                Err(err) => Err(err.context(warning.to_string())),
+            };
+        }
 
-        let mut res = self.get_link(title, volume, &url);
+        res
+    }
 
-        // No result with hyphen, try without it then!
-        if res.is_err() && title.contains('-') {
-            let title = title.replace("- ", "");
+    /// Return the language in which the book at `url` was found, if any.
+    pub(crate) fn found_in(&self, url: &Url) -> Option<Language> {
+        self.found_in.borrow().get(url).copied()
+    }
 
-            url = SEARCH_URL.clone();
-            url.query_pairs_mut()
-                .append_pair("csrf_token_bel", &csrf_token)
-                .append_pair("RechSerie", &normalize(&title))
-                .append_pair("RechLangue", "Français");
+    /// Record a minor issue met while locating the book at `url`.
+    fn warn(&self, url: &Url, warning: Warning) {
+        self.warnings
+            .borrow_mut()
+            .entry(url.clone())
+            .or_default()
+            .push(warning);
+    }
 
-            res = self.get_link(&title, volume, &url);
+    /// Find the book's URL on bedetheque, in the given language.
+    ///
+    /// If the primary search field gives nothing, the other one is tried.
+    fn find_book_in_language(
+        &self,
+        title: &str,
+        volume: Option<u8>,
+        language: Language,
+        csrf_token: &mut Option<String>,
+    ) -> Result<Url> {
+        let mut res = Err(anyhow!("no search field"));
+
+        for field in [self.search_field, self.search_field.alternate()] {
+            res = self.search_book(title, volume, language, field, csrf_token);
+
+            // No result with hyphens (or dashes), try other spellings then!
+            if res.is_err() && title.replace(DASHES, "-").contains('-') {
+                for title in hyphen_variants(title) {
+                    res = self.search_book(&title, volume, language, field, csrf_token);
+                    if res.is_ok() {
+                        break;
+                    }
+                }
+            }
+            if res.is_ok() {
+                break;
+            }
         }
 
         res
     }
 
+    /// Find the book's URL from the bedetheque page of its series.
+    pub(crate) fn find_book_in_series(&self, series: &Url, volume: Option<u8>) -> Result<Url> {
+        let key = Volume {
+            title: series.as_str().to_owned(),
+            volume,
+            language: None,
+        };
+
+        if let Some(url) = self.cached(&key) {
+            return Ok(url);
+        }
+
+        let mut res = None;
+        for (number, url) in self.get_albums(series)? {
+            if number == volume {
+                res = Some(url.clone());
+            }
+
+            let key = Volume {
+                title: series.as_str().to_owned(),
+                volume: number,
+                language: None,
+            };
+            self.cache.borrow_mut().insert(key, url);
+        }
+
+        res.ok_or_else(|| anyhow!("cannot find book in series {}", series.as_str()))
+    }
+
+    /// Get the volume numbers listed on a series page.
+    pub(crate) fn series_volumes(&self, series: &Url) -> Result<BTreeSet<u8>> {
+        Ok(self
+            .get_albums(series)?
+            .into_iter()
+            .filter_map(|(number, _)| number)
+            .collect())
+    }
+
     /// Extract metadata from the book's page.
+    ///
+    /// Metadata are cached, so books sharing the same page are only fetched
+    /// once.
     pub(crate) fn fetch_info(&self, url: &Url) -> Result<VolumeInfo> {
+        if let Some((fetched_at, info)) = self.info_cache.borrow().get(url) {
+            if fetched_at.elapsed() < INFO_CACHE_TTL {
+                return Ok(info.clone());
+            }
+        }
+
         let html = self.get_html(url)?;
+        let info = VolumeInfo::new(&html, &self.author_labels)
+            .with_context(|| format!("invalid page {}", url.as_str()))?;
+        self.info_cache
+            .borrow_mut()
+            .insert(url.clone(), (Instant::now(), info.clone()));
+
+        Ok(info)
+    }
+
+    /// Search the book on bedetheque using the `field` of the search form,
+    /// unless it's already in cache.
+    ///
+    /// The CSRF token is only fetched when needed, then reused.
+    fn search_book(
+        &self,
+        title: &str,
+        volume: Option<u8>,
+        language: Language,
+        field: SearchField,
+        csrf_token: &mut Option<String>,
+    ) -> Result<Url> {
+        let key = Volume {
+            title: title.to_owned(),
+            volume,
+            language: Some(language),
+        };
+
+        if let Some(url) = self.cached(&key) {
+            return Ok(url);
+        }
+
+        let csrf_token = match csrf_token {
+            Some(token) => token,
+            None => csrf_token.insert(self.get_csrf_token()?),
+        };
+        let mut url = SEARCH_URL.clone();
+        url.query_pairs_mut()
+            .append_pair("csrf_token_bel", csrf_token)
+            .append_pair(field.param(), &normalize(title))
+            .append_pair("RechLangue", language.search_value());
 
-        Ok(VolumeInfo::new(&html))
+        self.get_link(title, volume, language, &url)
     }
 
     /// Extract the CSRF token from the homepage.
     #[allow(clippy::filter_next)]
-    fn get_csrf_token(&self) -> Result<String> {
+    pub(crate) fn get_csrf_token(&self) -> Result<String> {
         let html = self.get_html(&MAIN_URL)?;
 
         Ok(CSRF_TOKEN_SELECTOR
@@ -114,8 +551,15 @@ impl Client {
     }
 
     /// Get the book's URLs from the search result of a the given series.
-    fn get_link(&self, title: &str, volume: Option<u8>, url: &Url) -> Result<Url> {
+    fn get_link(
+        &self,
+        title: &str,
+        volume: Option<u8>,
+        language: Language,
+        url: &Url,
+    ) -> Result<Url> {
         let mut res = None;
+        let mut matches = 0;
 
         let html = self.get_html(url)?;
         // First, look for an exact match.
@@ -123,6 +567,9 @@ impl Client {
             .filter(html.descendants().elements())
             .filter(|element| is_right_series(element.as_node(), title, true))
             .collect::<Vec<_>>();
+        // Only exact matches are reused for the other volumes: a prefix match
+        // may be another series (e.g. a spin-off).
+        let exact_match = !nodes.is_empty();
         // If none are found, fallback on prefix then...
         if nodes.is_empty() {
             nodes = LINKS_SELECTOR
@@ -140,38 +587,134 @@ impl Client {
 
             if number == volume {
                 res = Some(url.clone());
+                matches += 1;
+            } else if !exact_match {
+                continue;
             }
 
             let key = Volume {
                 title: title.to_owned(),
                 volume: number,
+                language: Some(language),
             };
             self.cache.borrow_mut().insert(key, url);
         }
 
-        res.ok_or_else(|| anyhow!("cannot find book on bedetheque"))
+        let res = res.ok_or_else(|| anyhow!("cannot find book on bedetheque"))?;
+        // Several books match, the last one is picked: tell the user.
+        if matches > 1 {
+            self.warn(&res, Warning::AmbiguousMatch(title.to_owned(), matches));
+        }
+        // Most wrong matches come from there: tell the user too.
+        if !exact_match {
+            self.warn(&res, Warning::PrefixMatch(title.to_owned()));
+        }
+
+        Ok(res)
+    }
+
+    /// Get the albums (number and URL) listed on a series page.
+    fn get_albums(&self, series: &Url) -> Result<Vec<(Option<u8>, Url)>> {
+        let html = self.get_html(series)?;
+
+        ALBUMS_SELECTOR
+            .filter(html.descendants().elements())
+            .map(|node| {
+                let attributes = node.attributes.borrow();
+                let link = attributes.get("href").context("album URL not found")?;
+                let url =
+                    Url::parse(link).with_context(|| format!("invalid album URL `{link}`"))?;
+                let title = attributes.get("title").unwrap_or_default();
+                let number = ALBUM_NUMBER_REGEX
+                    .captures(title)
+                    .map(|captures| {
+                        captures
+                            .name("volume")
+                            .expect("invalid capture group for volume")
+                            .as_str()
+                            .parse::<u8>()
+                            .context("invalid album number")
+                    })
+                    .transpose()?;
+
+                Ok((number, url))
+            })
+            .collect()
     }
 
     /// Retrieve and parse the page at `url`.
     fn get_html(&self, url: &Url) -> Result<kuchiki::NodeRef> {
-        // Don't get banned from bedetheque...
-        thread::sleep(Duration::new(2, 0));
+        let mut stats = self.stats.get();
+        let replay = matches!(self.tape, Some(Tape::Replay(_)));
+        if !replay && self.max_requests.is_some_and(|max| stats.requests >= max) {
+            return Err(BudgetExhausted.into());
+        }
+        stats.requests += 1;
+        self.stats.set(stats);
+
+        let html = if let Some(Tape::Replay(dir)) = &self.tape {
+            tape::load(dir, url)?
+        } else {
+            // Don't get banned from bedetheque...
+            thread::sleep(Duration::new(2, 0));
+
+            let response = match self
+                .agent
+                .request_url("GET", url)
+                .set("accept", "text/html")
+                .set("Referer", MAIN_URL.as_str())
+                .call()
+            {
+                Ok(response) => response,
+                Err(ureq::Error::Status(code, response)) => {
+                    return Err(HttpStatus::new(code, response))
+                        .with_context(|| format!("failed to get {}", url.as_str()));
+                }
+                Err(err) => {
+                    return Err(err).with_context(|| format!("failed to get {}", url.as_str()))
+                }
+            };
 
-        let response = self
-            .agent
-            .request_url("GET", url)
-            .set("accept", "text/html")
-            .set("Referer", MAIN_URL.as_str())
-            .call()?;
+            response
+                .into_string()
+                .with_context(|| format!("failed to read HTML from {}", url.as_str()))?
+        };
+        if let Some(Tape::Record(dir)) = &self.tape {
+            tape::save(dir, url, &html)?;
+        }
 
-        let html = response
-            .into_string()
-            .with_context(|| format!("failed to read HTML from {}", url.as_str()))?;
+        let mut stats = self.stats.get();
+        stats.bytes += html.len() as u64;
+        self.stats.set(stats);
 
         Ok(kuchiki::parse_html().one(html))
     }
 }
 
+impl MetadataProvider for Client {
+    fn locate(&self, book: &Lookup<'_>) -> Result<Url> {
+        match book.series {
+            Some(SeriesConfig { url: Some(url), .. }) => self.find_book_in_series(url, book.volume),
+            Some(SeriesConfig {
+                title: Some(title), ..
+            }) => self.find_book(title, book.volume),
+            _ => self.find_book(book.title, book.volume),
+        }
+    }
+
+    fn metadata(&self, url: &Url) -> Result<VolumeInfo> {
+        self.fetch_info(url)
+    }
+
+    fn language(&self, url: &Url) -> Option<&'static str> {
+        self.found_in(url).map(Language::code)
+    }
+
+    fn warnings(&self, url: &Url) -> Vec<Warning> {
+        self.warnings.borrow_mut().remove(url).unwrap_or_default()
+    }
+}
+
 /// Extract the book number, if any, from the book link.
 #[allow(clippy::filter_next)]
 fn get_book_number(node: &kuchiki::NodeRef) -> Result<Option<u8>> {
@@ -192,26 +735,282 @@ fn get_book_number(node: &kuchiki::NodeRef) -> Result<Option<u8>> {
 }
 
 /// Check if the series under `node` is the right one (i.e. matches `title`).
+///
+/// A qualifier (e.g. the publisher in `Naruto (Kana)`) is optional: it only
+/// has to match when `title` has one.
 #[allow(clippy::filter_next)]
 fn is_right_series(node: &kuchiki::NodeRef, title: &str, exact_match: bool) -> bool {
-    let title = normalize(title);
+    let titles = match_forms(title);
 
     match TITLE_SELECTOR.filter(node.descendants().elements()).next() {
         Some(node) => {
-            let text = node.text_contents().replace('!', "");
-            let text = text.trim().to_lowercase();
+            let text = normalize(&node.text_contents());
+            let texts = [text.as_str(), strip_qualifier(&text)];
+
+            titles.iter().any(|title| {
+                texts.iter().any(|text| {
+                    if exact_match {
+                        text == title
+                    } else {
+                        text.starts_with(title.as_str())
+                    }
+                })
+            })
+        }
+        None => false,
+    }
+}
 
-            if exact_match {
-                text == title
+/// Return the (normalized) titles searched on bedetheque for `title`, in the
+/// order they are tried.
+pub(crate) fn search_forms(title: &str) -> Vec<String> {
+    let mut forms = vec![normalize(title)];
+    for variant in hyphen_variants(title) {
+        let variant = normalize(&variant);
+        if !forms.contains(&variant) {
+            forms.push(variant);
+        }
+    }
+
+    forms
+}
+
+/// Return the (normalized) titles a series must match for `title`.
+pub(crate) fn match_forms(title: &str) -> Vec<String> {
+    title_forms(&normalize(title))
+}
+
+/// Remove the trailing parenthesized qualifier of a (normalized) title, if
+/// any: `naruto (kana)` becomes `naruto`.
+fn strip_qualifier(title: &str) -> &str {
+    if !title.ends_with(')') {
+        return title;
+    }
+
+    match title.rfind(" (") {
+        Some(start) if start > 0 => &title[..start],
+        _ => title,
+    }
+}
+
+/// Return the title a book of the series `series_title` (as listed on
+/// bedetheque) should be named with, the book being titled `title`.
+///
+/// The leading article moved at the end is put back (e.g. `Aventures de
+/// Tintin, Les` becomes `Les Aventures de Tintin`), and the qualifier is
+/// dropped unless `title` has one.
+pub(crate) fn canonical_title(series_title: &str, title: &str) -> String {
+    let moved = series_title.rsplit_once(", ").or_else(|| {
+        series_title
+            .strip_suffix(')')
+            .and_then(|rest| rest.rsplit_once(" ("))
+    });
+    let series_title = match moved {
+        Some((rest, article)) if ARTICLES.contains(&article.to_lowercase().as_str()) => {
+            let separator = if article.ends_with(['\'', '’']) {
+                ""
             } else {
-                text.starts_with(&title)
-            }
+                " "
+            };
+            format!("{article}{separator}{rest}")
+        }
+        _ => series_title.to_owned(),
+    };
+
+    if strip_qualifier(title) == title {
+        strip_qualifier(&series_title).to_owned()
+    } else {
+        series_title
+    }
+}
+
+/// Forms under which bedetheque may list a (normalized) title.
+///
+/// Besides the natural order, the leading article can be moved at the end
+/// (e.g. `les aventures de tintin` => `aventures de tintin, les`).
+fn title_forms(title: &str) -> Vec<String> {
+    let mut forms = vec![title.to_owned()];
+
+    let moved = ARTICLES.iter().find_map(|article| {
+        let rest = title.strip_prefix(article)?;
+        // Elided articles are glued to the next word, the others aren't.
+        let rest = if article.ends_with(['\'', '’']) {
+            rest
+        } else {
+            rest.strip_prefix(' ')?
+        };
+        Some((*article, rest.trim_start()))
+    });
+    if let Some((article, rest)) = moved {
+        if !rest.is_empty() {
+            forms.push(format!("{rest}, {article}"));
+            forms.push(format!("{rest} ({article})"));
         }
-        None => false,
     }
+
+    forms
+}
+
+/// Alternative spellings of a hyphenated title, in the order to try them.
+fn hyphen_variants(title: &str) -> Vec<String> {
+    let title = title.replace(DASHES, "-");
+    let mut variants = Vec::new();
+
+    for variant in [
+        // Drop dangling hyphens (e.g. `Title - Subtitle`).
+        title.replace("- ", ""),
+        // Drop every hyphen (e.g. `Spider-Man` => `SpiderMan`).
+        title.replace('-', ""),
+        // Hyphens as spaces (e.g. `Spider-Man` => `Spider Man`).
+        title.replace('-', " "),
+    ] {
+        let variant = variant.split_whitespace().collect::<Vec<_>>().join(" ");
+        if variant != title && !variants.contains(&variant) {
+            variants.push(variant);
+        }
+    }
+
+    variants
 }
 
 /// Normalize the series' title for bedetheque.
+///
+/// Used on both sides (search and match), so that punctuation stripped by
+/// bedetheque, or a different dash, doesn't prevent a match.
 fn normalize(title: &str) -> String {
-    title.to_lowercase()
+    title
+        .to_lowercase()
+        .replace(STRIPPED_CHARS, " ")
+        .replace(DASHES, "-")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_dashes() {
+        assert_eq!(normalize("Astérix – Le Domaine"), "astérix - le domaine");
+        assert_eq!(normalize("Astérix — Le Domaine"), "astérix - le domaine");
+        assert_eq!(normalize("Spider‐Man"), "spider-man");
+    }
+
+    #[test]
+    fn hyphen_variants_dashes() {
+        assert_eq!(
+            hyphen_variants("Spider–Man"),
+            vec!["SpiderMan".to_owned(), "Spider Man".to_owned()]
+        );
+        assert_eq!(
+            hyphen_variants("Title — Subtitle"),
+            vec!["Title Subtitle".to_owned()]
+        );
+    }
+
+    #[test]
+    fn title_forms_dashes() {
+        assert_eq!(
+            match_forms("Les Aventures — Tintin"),
+            vec![
+                "les aventures - tintin".to_owned(),
+                "aventures - tintin, les".to_owned(),
+                "aventures - tintin (les)".to_owned(),
+            ]
+        );
+        assert_eq!(
+            title_forms(&normalize("La Quête – Origines")),
+            title_forms(&normalize("La Quête - Origines"))
+        );
+        assert_eq!(match_forms("Spider–Man"), match_forms("Spider-Man"));
+        assert_eq!(search_forms("Spider–Man"), search_forms("Spider-Man"));
+    }
+
+    /// Return a search result listing the series `title`.
+    fn series_node(title: &str) -> kuchiki::NodeRef {
+        kuchiki::parse_html().one(format!(r#"<a><span class="serie">{title}</span></a>"#))
+    }
+
+    #[test]
+    fn strip_qualifiers() {
+        assert_eq!(strip_qualifier("naruto (kana)"), "naruto");
+        assert_eq!(strip_qualifier("naruto"), "naruto");
+        // Nothing left without the qualifier.
+        assert_eq!(strip_qualifier("(kana)"), "(kana)");
+        // Not trailing.
+        assert_eq!(strip_qualifier("naruto (kana) tome"), "naruto (kana) tome");
+    }
+
+    #[test]
+    fn right_series_qualified() {
+        let node = series_node("Naruto (Kana)");
+
+        // The qualifier is optional...
+        assert!(is_right_series(&node, "Naruto", true));
+        assert!(is_right_series(&node, "Naruto (Kana)", true));
+        // ...but must match when given.
+        assert!(!is_right_series(&node, "Naruto (Glénat)", true));
+        assert!(!is_right_series(&node, "Boruto", true));
+        assert!(is_right_series(&node, "Naru", false));
+    }
+
+    #[test]
+    fn normalize_punctuation() {
+        assert_eq!(normalize("Ranma ½ !"), "ranma ½");
+        assert_eq!(normalize("Qui a tué Kennedy ?"), "qui a tué kennedy");
+        assert_eq!(normalize("Star Wars: Legacy"), "star wars legacy");
+        assert_eq!(normalize("Star Wars : Legacy"), "star wars legacy");
+        assert_eq!(normalize("Wanted!?"), "wanted");
+        // Same form on both sides, whatever the spacing around punctuation.
+        assert_eq!(
+            match_forms("Star Wars: Legacy"),
+            search_forms("Star Wars : Legacy")
+        );
+    }
+
+    #[test]
+    fn title_forms_moved_article() {
+        assert_eq!(
+            title_forms("les aventures de tintin"),
+            vec![
+                "les aventures de tintin".to_owned(),
+                "aventures de tintin, les".to_owned(),
+                "aventures de tintin (les)".to_owned(),
+            ]
+        );
+        assert_eq!(
+            title_forms("l'épervier"),
+            vec![
+                "l'épervier".to_owned(),
+                "épervier, l'".to_owned(),
+                "épervier (l')".to_owned(),
+            ]
+        );
+        assert_eq!(
+            title_forms("l’épervier"),
+            vec![
+                "l’épervier".to_owned(),
+                "épervier, l’".to_owned(),
+                "épervier (l’)".to_owned(),
+            ]
+        );
+        // Not an article.
+        assert_eq!(title_forms("lesbos"), vec!["lesbos".to_owned()]);
+        assert_eq!(title_forms("les"), vec!["les".to_owned()]);
+    }
+
+    #[test]
+    fn right_series_moved_article() {
+        let node = series_node("Aventures de Tintin (Les)");
+        assert!(is_right_series(&node, "Les Aventures de Tintin", true));
+
+        let node = series_node("Aventures de Tintin, Les");
+        assert!(is_right_series(&node, "Les Aventures de Tintin", true));
+        assert!(!is_right_series(&node, "Aventures de Tintin", true));
+
+        let node = series_node("Épervier, L'");
+        assert!(is_right_series(&node, "L'Épervier", true));
+    }
 }
@@ -0,0 +1,84 @@
+//! Persistent cache of the bedetheque lookups, shared between runs.
+//!
+//! The cache file is protected by an advisory lock (on a lock file next to
+//! it), so that concurrent runs don't clobber each other: reads take a shared
+//! lock, writes an exclusive one. Writes go through a temporary file renamed
+//! over the cache, so that an interrupted run can't leave it truncated.
+
+use crate::bedetheque::Volume;
+use anyhow::{Context, Result};
+use fs2::FileExt;
+use std::{
+    collections::HashMap,
+    fs::{self, File, OpenOptions},
+    io,
+    path::{Path, PathBuf},
+};
+use url::Url;
+
+/// Cached lookups: URL of each volume.
+pub(crate) type Lookups = Vec<(Volume, Url)>;
+
+/// Load the cached lookups from `path`, if it exists.
+pub(crate) fn load(path: &Path) -> Result<Lookups> {
+    let lock = lock(path)?;
+    FileExt::lock_shared(&lock)
+        .with_context(|| format!("failed to lock cache {}", path.display()))?;
+
+    read(path)
+}
+
+/// Save the `lookups` to `path`.
+///
+/// Lookups saved by concurrent runs in the meantime are kept.
+pub(crate) fn save(path: &Path, lookups: Lookups) -> Result<()> {
+    let lock = lock(path)?;
+    FileExt::lock_exclusive(&lock)
+        .with_context(|| format!("failed to lock cache {}", path.display()))?;
+
+    // Merge with the current content, our lookups being the most recent.
+    let mut merged = read(path)?.into_iter().collect::<HashMap<_, _>>();
+    merged.extend(lookups);
+    let content = serde_json::to_string(&merged.into_iter().collect::<Lookups>())
+        .context("failed to serialize cache")?;
+
+    let tmp_path = path.with_extension("tmp");
+    fs::write(&tmp_path, content)
+        .and_then(|()| fs::rename(&tmp_path, path))
+        .with_context(|| format!("failed to write cache {}", path.display()))
+}
+
+/// Open the lock file of the cache at `path`, creating it if needed.
+fn lock(path: &Path) -> Result<File> {
+    let lock_path = lock_path(path);
+
+    OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(&lock_path)
+        .with_context(|| format!("failed to open cache lock {}", lock_path.display()))
+}
+
+/// Return the path of the lock file kept next to the `cache` file.
+fn lock_path(cache: &Path) -> PathBuf {
+    cache.with_extension("lock")
+}
+
+/// Read the lookups from the cache file at `path` (locked), if it exists.
+fn read(path: &Path) -> Result<Lookups> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => {
+            return Err(err).with_context(|| format!("failed to read cache {}", path.display()))
+        }
+    };
+
+    // A new cache file is empty.
+    if content.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+
+    serde_json::from_str(&content).with_context(|| format!("invalid cache {}", path.display()))
+}
@@ -0,0 +1,107 @@
+//! Catalog of the books' expected metadata, from a CSV file, for books that
+//! aren't on bedetheque.
+
+use crate::{
+    archive,
+    metadata::VolumeInfo,
+    provider::{Lookup, MetadataProvider, NoMetadata},
+};
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs,
+    path::Path,
+};
+use url::Url;
+
+/// A row of the catalog.
+#[derive(Debug, Deserialize)]
+struct Row {
+    /// File name of the book.
+    file: String,
+    /// Authors, `-`-separated as in the file names.
+    authors: String,
+    /// Publication year, if known.
+    year: Option<u16>,
+}
+
+/// Catalog of the books' expected metadata.
+#[derive(Debug)]
+pub(crate) struct Catalog {
+    /// URL of the catalog file, to build the books' URL from.
+    url: Url,
+    /// Index of each book, by normalized file name.
+    books: HashMap<String, usize>,
+    /// Metadata of each book, in the catalog's order.
+    metadata: Vec<VolumeInfo>,
+}
+
+impl Catalog {
+    /// Load the catalog from the CSV file at `path`.
+    ///
+    /// The file has a header, and three columns: `file`, `authors` and `year`.
+    pub(crate) fn load(path: &Path) -> Result<Self> {
+        let url = fs::canonicalize(path)
+            .ok()
+            .and_then(|path| Url::from_file_path(path).ok())
+            .with_context(|| format!("cannot locate catalog {}", path.display()))?;
+        let mut reader = csv::Reader::from_path(path)
+            .with_context(|| format!("failed to read catalog {}", path.display()))?;
+        let mut books = HashMap::new();
+        let mut metadata = Vec::new();
+
+        for row in reader.deserialize::<Row>() {
+            let row = row.with_context(|| format!("invalid catalog {}", path.display()))?;
+            books.insert(normalize(&row.file), metadata.len());
+            metadata.push(VolumeInfo {
+                authors: row.authors,
+                names: Vec::new(),
+                years: row.year.into_iter().collect::<BTreeSet<_>>(),
+                series: None,
+                volume: None,
+                series_title: None,
+            });
+        }
+
+        Ok(Self {
+            url,
+            books,
+            metadata,
+        })
+    }
+}
+
+impl MetadataProvider for Catalog {
+    fn locate(&self, book: &Lookup<'_>) -> Result<Url> {
+        let index = self
+            .books
+            .get(&normalize(book.file_name))
+            // Checked offline then.
+            .ok_or(NoMetadata)?;
+
+        // Each book is identified by its index in the catalog.
+        let mut url = self.url.clone();
+        url.set_fragment(Some(&index.to_string()));
+        Ok(url)
+    }
+
+    fn metadata(&self, url: &Url) -> Result<VolumeInfo> {
+        url.fragment()
+            .and_then(|index| index.parse::<usize>().ok())
+            .and_then(|index| self.metadata.get(index))
+            .cloned()
+            .with_context(|| format!("no metadata in catalog for {}", url.as_str()))
+    }
+}
+
+/// Normalize a file name, to be lenient on the case and the extension.
+fn normalize(file_name: &str) -> String {
+    let file_name = file_name.trim().to_lowercase();
+
+    archive::BOOK_EXTENSIONS
+        .iter()
+        .find_map(|extension| file_name.strip_suffix(&format!(".{extension}")))
+        .unwrap_or(&file_name)
+        .to_owned()
+}
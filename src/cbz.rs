@@ -1,33 +1,50 @@
 //! CBZ check implementation.
 
-use crate::{bedetheque, error::Error};
+use crate::{
+    archive::{self, ReadSeek},
+    authors, bedetheque,
+    cli::{AuthorOrder, Options, YearPolicy},
+    comicinfo,
+    config::Severity,
+    error::Error,
+    filename::{self, ParsedName},
+    jpeg,
+    metadata::VolumeInfo,
+    picture::{self, Picture},
+    provider::{Lookup, MetadataProvider, NoMetadata},
+    sample,
+    series::SeriesConfig,
+    warning::{SkipReason, Warning},
+};
 use anyhow::{bail, Context, Result};
+use imagesize::ImageType;
 use once_cell::sync::Lazy;
-use regex::Regex;
 use std::{
+    collections::BTreeMap,
     ffi::OsStr,
     fs,
-    io::{BufReader, Cursor},
+    io::{Cursor, Read},
+    iter, mem,
     path::{Path, PathBuf},
+    time::{Duration, Instant},
 };
 use url::Url;
-use zip::{read::ZipFile, DateTime, ZipArchive};
+use zip::{DateTime, ZipArchive};
 
-/// Regex to extract info from the name of a series' book.
-static SERIES_REGEX: Lazy<Regex> = Lazy::new(|| {
-    Regex::new(
-            r#"^(?P<title>.+)(?: T(?P<volume>[0-9]+)) \((?P<authors>.+)\) \((?P<year>[0-9]{4})\) \[\w+-(?P<width>[0-9]+)\]"#,
-        )
-        .expect("valid series regexp")
-});
+/// Extensions of the images, in lowercase.
+const IMAGE_EXTENSIONS: &[&str] = &[
+    "jpg", "jpeg", "png", "gif", "webp", "bmp", "tif", "tiff", "avif", "jxl",
+];
+
+/// Extensions of the images whose format can't be inspected, by `imagesize`
+/// nor the enabled `image` decoders.
+const UNSUPPORTED_EXTENSIONS: &[&str] = &["bmp", "tif", "tiff", "avif", "jxl"];
+
+/// Bytes tolerated after the image data, as padding.
+const MAX_PADDING: usize = 16;
 
-/// Regex to extract info from the name of a one-shot.
-static ONESHOT_REGEX: Lazy<Regex> = Lazy::new(|| {
-    Regex::new(
-        r#"^(?P<title>.+) \((?P<authors>.+)\) \((?P<year>[0-9]{4})\) \[\w+-(?P<width>[0-9]+)\]"#,
-    )
-    .expect("valid one-shot regexp")
-});
+/// Files left by operating systems, never expected in an archive.
+const JUNK_FILES: &[&str] = &["Thumbs.db", "desktop.ini", ".DS_Store"];
 
 /// Expected modified date.
 static EXPECTED_DATE: Lazy<DateTime> =
@@ -36,111 +53,624 @@ static EXPECTED_DATE: Lazy<DateTime> =
 #[derive(Debug)]
 pub(crate) struct Book {
     path: PathBuf,
-    url: Url,
+    /// File name, as UTF-8 string.
+    name: String,
+    /// Collection archive containing the book, and the book's entry in it, if
+    /// any.
+    container: Option<(PathBuf, String)>,
+    /// URL of the book's metadata, unless checked offline.
+    url: Option<Url>,
+    /// Whether the metadata source has no metadata for the book, which is
+    /// then checked offline.
+    no_metadata: bool,
+    /// Language in which the book was found, if known.
+    language: Option<&'static str>,
+    title: String,
     authors: String,
+    volume: Option<u8>,
     year: u16,
     width: usize,
+    /// Widths accepted in addition to `width`, from the series configuration.
+    extra_widths: Vec<usize>,
+    /// Time spent looking for the book's metadata.
+    lookup_time: Duration,
+}
+
+/// Time spent in each step of a book's check.
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct Timings {
+    /// Looking for the book's metadata.
+    pub(crate) find_book: Duration,
+    /// Retrieving the book's metadata.
+    pub(crate) fetch_info: Duration,
+    /// Checking the images of the book.
+    pub(crate) images: Duration,
+    /// Whole check, lookup included.
+    pub(crate) total: Duration,
+}
+
+/// Outcome of a book's check.
+#[derive(Default)]
+pub(crate) struct Report {
+    /// Errors detected, if any.
+    pub(crate) errors: Vec<Error>,
+    /// Minor issues detected, that don't fail the check.
+    pub(crate) warnings: Vec<Warning>,
+    /// Time spent in each step of the check.
+    pub(crate) timings: Timings,
+    /// URL of the book's series on bedetheque, if known.
+    pub(crate) series: Option<Url>,
+}
+
+/// Information about a page, for book-level checks.
+struct Page {
+    /// Name of the archive entry.
+    name: String,
+    /// Image format, if detected.
+    format: Option<ImageType>,
+    /// Bits per pixel, if detected.
+    bit_depth: Option<u16>,
+    /// Chroma subsampling, for color JPEG images.
+    subsampling: Option<String>,
+    /// Format that required the fallback decoder, if any.
+    fallback: Option<String>,
+    /// Width, in pixels.
+    width: usize,
+    /// Height, in pixels.
+    height: usize,
+    /// Size of the image file, in bytes.
+    size: usize,
 }
 
 impl Book {
     /// Initialize a new book by extracting information from its name.
-    pub(crate) fn new(client: &bedetheque::Client, path: &Path) -> Result<Self> {
-        let filename = get_file_name(path);
+    ///
+    /// If a series configuration is given, it's used to locate the book on
+    /// bedetheque.
+    ///
+    /// Without metadata provider, the book isn't looked up at all.
+    ///
+    /// If `underscores_as_spaces` is set, underscores in the file name are
+    /// read as spaces (the file name is still reported as is).
+    ///
+    /// A directory is an unpacked book, named after the directory.
+    pub(crate) fn new(
+        provider: Option<&dyn MetadataProvider>,
+        path: &Path,
+        series: Option<&SeriesConfig>,
+        underscores_as_spaces: bool,
+    ) -> Result<Self> {
+        let filename = get_file_name(path).context(SkipReason::Unreadable)?;
 
-        if path.extension() != Some(OsStr::new("cbz")) {
-            bail!("not a CBZ")
+        if !archive::is_book(path) && !path.is_dir() {
+            bail!(SkipReason::NotCbz)
         }
 
-        let captures = if let Some(captures) = SERIES_REGEX.captures(filename) {
-            captures
-        } else if let Some(captures) = ONESHOT_REGEX.captures(filename) {
-            captures
+        let filename = if underscores_as_spaces {
+            filename.replace('_', " ")
         } else {
-            bail!("cannot extract info from filename")
+            filename.to_owned()
         };
+        let parsed = filename::parse_filename(&filename).context(SkipReason::UnparseableName)?;
+
+        Self::new_from_parsed(provider, path.to_owned(), parsed, series)
+    }
+
+    /// Mark the book as the entry `entry` of the collection archive
+    /// `container`.
+    ///
+    /// The reported name is prefixed with the container's.
+    pub(crate) fn in_container(mut self, container: &Path, entry: &str) -> Self {
+        let container_name = container
+            .file_name()
+            .map(OsStr::to_string_lossy)
+            .unwrap_or_default();
+        self.name = format!("{container_name}/{}", self.name);
+        self.container = Some((container.to_owned(), entry.to_owned()));
+        self
+    }
+
+    /// Return the path of the book.
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
 
-        Self::new_from_captures(client, path.to_owned(), &captures)
+    /// Return the collection archive containing the book, and the book's entry
+    /// in it, if any.
+    pub(crate) fn container(&self) -> Option<(&Path, &str)> {
+        self.container
+            .as_ref()
+            .map(|(container, entry)| (container.as_path(), entry.as_str()))
     }
 
     /// Return the file name of the book.
     pub(crate) fn file_name(&self) -> &str {
-        get_file_name(&self.path)
+        &self.name
     }
 
-    /// Return the bedetheque URL used to check the metadata.
-    pub(crate) fn ref_url(&self) -> &Url {
-        &self.url
+    /// Return the volume number of the book, if any.
+    pub(crate) fn volume(&self) -> Option<u8> {
+        self.volume
     }
 
-    /// Check the book and return a list of errors if any.
-    pub(crate) fn check(&self, client: &bedetheque::Client) -> Result<Vec<Error>> {
-        let mut errors = Vec::new();
-        let fp = fs::File::open(&self.path).context("open error")?;
-        let mut cbz = ZipArchive::new(fp).context("read error")?;
+    /// Return the URL used to check the metadata, if any.
+    pub(crate) fn ref_url(&self) -> Option<&Url> {
+        self.url.as_ref()
+    }
 
-        self.check_book_metadata(client, &mut errors)?;
-        for i in 0..cbz.len() {
-            let mut entry = cbz.by_index(i).context("failed to read ZIP entry")?;
+    /// Return the language in which the book was found, if known.
+    pub(crate) fn language(&self) -> Option<&str> {
+        self.language
+    }
 
-            if !entry.is_file() {
-                continue;
-            }
+    /// Explain why an error was reported for this book, and how to fix it.
+    #[allow(clippy::too_many_lines)] // One arm per error, nothing to factorize.
+    pub(crate) fn explain(&self, err: &Error) -> String {
+        match err {
+            Error::Authors(expected, _) => format!(
+                "The authors in the file name ({}) must match the ones listed on bedetheque, \
+                 writers first then pencillers. Rename the file with ({expected}).",
+                self.authors
+            ),
+            Error::AuthorOrder(expected) => format!(
+                "The authors in the file name ({}) must be listed in the same order as on \
+                 bedetheque, writers first then pencillers. Rename the file with ({expected}).",
+                self.authors
+            ),
+            Error::Year(_) => format!(
+                "The year in the file name ({}) must be the legal deposit year of one of the \
+                 editions listed on bedetheque. Rename the file with the right year.",
+                self.year
+            ),
+            Error::Width => self.explain_width(),
+            Error::Date => format!(
+                "Every file in the archive must be last modified on {:04}-{:02}-{:02}. Reset the \
+                 dates before packing the archive.",
+                EXPECTED_DATE.year(),
+                EXPECTED_DATE.month(),
+                EXPECTED_DATE.day()
+            ),
+            Error::Exif => "Images must not embed EXIF metadata (except the tags allowed in the \
+                            configuration). Strip the metadata before \
+                            packing the archive."
+                .to_owned(),
+            Error::Dpi(entry, _) => format!(
+                "The DPI declared by {entry} doesn't match a {}px wide image printed at the given \
+                 page width. Fix or strip the DPI metadata.",
+                self.width
+            ),
+            Error::MissingVolume(volume) => format!(
+                "Volume {volume} is listed on bedetheque, but no file of the series has it. Add \
+                 the missing volume."
+            ),
+            Error::ProgressiveJpeg(entry) => format!(
+                "JPEG images must use baseline encoding, but {entry} is progressive. Re-encode \
+                 it as baseline."
+            ),
+            Error::BaselineJpeg(entry) => format!(
+                "JPEG images must use progressive encoding, but {entry} is baseline. Re-encode \
+                 it as progressive."
+            ),
+            Error::TinyImage(entry, _) => format!(
+                "Images must not be tiny, a few hundred bytes is most likely a corrupt or \
+                 empty page. Export {entry} again."
+            ),
+            Error::CoverOrientation(entry) => format!(
+                "The cover must be in portrait orientation, a landscape cover is most likely \
+                 a misfiled spread. Check that {entry} is the right cover, or allow \
+                 wraparound covers with --allow-wraparound-cover."
+            ),
+            Error::InconsistentExtensionCase(_) => "The extensions of the images must use the \
+                                                    same case (e.g. `.jpg`, not `.JPG`). \
+                                                    Rename the listed images."
+                .to_owned(),
+            Error::MixedBitDepth(_) => "Every image of the book must have the same bit depth \
+                                         (e.g. 8-bit grayscale). Export the listed images \
+                                         with the same settings as the other ones."
+                .to_owned(),
+            Error::CorruptArchive(_) => "The archive is damaged (e.g. truncated during a \
+                                          transfer): its index disagrees with its content. \
+                                          Copy or pack the book again."
+                .to_owned(),
+            Error::VolumeMismatch(found) => format!(
+                "The bedetheque page checked against is for volume {found}, not volume {}. \
+                 The lookup cache may be stale: remove it, or fix the series URL.",
+                self.volume.unwrap_or_default()
+            ),
+            Error::FilenameMismatch(expected) => format!(
+                "The file name must be derived from the bedetheque metadata (title, volume, \
+                 authors and year). Rename the file to {expected}."
+            ),
+            Error::FirstEditionYear(first) => format!(
+                "The year in the file name ({}) must be the legal deposit year of the first \
+                 edition listed on bedetheque. Rename the file with {first}.",
+                self.year
+            ),
+            Error::Orientation(entry) => format!(
+                "The EXIF orientation of {entry} asks readers to rotate or flip it, readers \
+                 that ignore it display the page sideways. Rotate the pixels instead, and strip \
+                 the orientation tag."
+            ),
+            Error::ExtraFiles(_) => "Archives must only contain the images, and the files \
+                                      allowed in the configuration (by default \
+                                      `ComicInfo.xml`). Remove the listed files, or allow \
+                                      them in the `[archive]` section."
+                .to_owned(),
+            Error::TooManyPages(_) => "The archive has more entries than allowed by \
+                                        --max-pages, it's most likely malformed. Check its \
+                                        content, or raise the limit."
+                .to_owned(),
+            Error::SuspiciouslySmall(_, _) => format!(
+                "The archive is much smaller than expected for its pages {}px wide, they are \
+                 probably over-compressed or incomplete. Export them again from a better \
+                 source, or lower `size_factor` in the `[images]` section.",
+                self.width
+            ),
+            Error::OddDimension(entry, _, _) => format!(
+                "Images must have an even width and height, some devices and readers can't \
+                 display odd dimensions. Crop or resize {entry} by one pixel."
+            ),
+            Error::Height(entry, _) => format!(
+                "Every image must be as high as one of the heights given to --height-ladder. \
+                 Resize {entry} to one of them."
+            ),
+            Error::EmbeddedThumbnail(entry) => format!(
+                "Images must not embed a thumbnail, it wastes space. Strip it from {entry} (e.g. \
+                 `exiftool -ThumbnailImage= {entry}`)."
+            ),
+            Error::MixedSubsampling(_) => "Every color JPEG image of the book must use the \
+                                            same chroma subsampling (e.g. 4:2:0). Export the \
+                                            listed images with the same settings as the other \
+                                            ones."
+                .to_owned(),
+            Error::TooFewPages(_) => "The book has fewer pages than required by --min-pages, \
+                                      the download or the export was most likely \
+                                      interrupted. Get it again, or lower the limit."
+                .to_owned(),
+            Error::CmykImage(_) => "CMYK JPEG images are rendered with wrong colors (e.g. \
+                                    inverted, or blue) by many readers. Convert the image \
+                                    to RGB."
+                .to_owned(),
+            Error::SizeOutlier(entry, _, _) => format!(
+                "Pages must weigh about as much as the other ones, a much larger page was most \
+                 likely left uncompressed or exported at a higher resolution. Export {entry} \
+                 again with the same settings as the other ones, or raise `outlier_factor` in \
+                 the `[images]` section."
+            ),
+            Error::CoverIsSpread(entry) => format!(
+                "The first page must be a single page, readers showing it alone display a \
+                 double-page spread shrunk. Split {entry}, or allow wraparound covers with \
+                 --allow-wraparound-cover."
+            ),
+            Error::ComicInfoInvalid(_) => "ComicInfo.xml must be well-formed XML, following \
+                                            the ComicInfo schema: readers silently ignore \
+                                            invalid metadata. Fix the reported element."
+                .to_owned(),
+            Error::TrailingGarbage(entry, _) => format!(
+                "Images must end with their image data, extra bytes bloat the book and confuse \
+                 some readers. Export {entry} again, or strip what follows the image data."
+            ),
+            Error::AnimatedImage(entry) => format!(
+                "A page must be a still image, {entry} has several frames (animated GIF or \
+                 APNG). Replace it with the intended still image."
+            ),
+            Error::UnsupportedFormat(entry) => format!(
+                "Images must be JPEG, PNG, GIF or WebP, the format of {entry} can't be checked. \
+                 Convert it to the format of the other pages."
+            ),
+            Error::MixedFormats(_) => "Every image of the book must use the same format \
+                                        (e.g. JPEG). Convert the listed images to the format of \
+                                        the other ones."
+                .to_owned(),
+            Error::LowQuality(entry, _) => format!(
+                "The estimated JPEG quality of {entry} is below the requested minimum, it was \
+                 probably compressed too much. Export it again from a better source."
+            ),
+        }
+    }
 
-            if !check_date(entry.last_modified()) {
-                errors.push(Error::Date);
-                // We found an error, we can stop here.
-                break;
-            }
-            if !self.check_image(&mut entry, &mut errors)? {
-                // We found an error, we can stop here.
-                break;
+    /// Explain the width error.
+    fn explain_width(&self) -> String {
+        if self.extra_widths.is_empty() {
+            return format!(
+                "Every image must be {}px wide, as declared in the file name (or about {}px for \
+                 double pages). Resize the images, or fix the width in the file name.",
+                self.width,
+                2 * self.width
+            );
+        }
+
+        format!(
+            "Every image must be {}px wide, as declared in the file name, or one of the widths \
+             from series.toml ({}px), or about twice as large for double pages. Resize the \
+             images, or fix the widths.",
+            self.width,
+            self.extra_widths
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join("px, ")
+        )
+    }
+
+    /// Check the book and report the errors, if any.
+    ///
+    /// Without metadata provider, the metadata are not checked.
+    pub(crate) fn check(
+        &self,
+        provider: Option<&dyn MetadataProvider>,
+        opts: &Options,
+    ) -> Result<Report> {
+        let start = Instant::now();
+        let mut report = Report::default();
+
+        if let (Some(provider), Some(url)) = (provider, &self.url) {
+            let now = Instant::now();
+            report.warnings.extend(provider.warnings(url));
+            report.series = self.check_book_metadata(provider, url, opts, &mut report)?;
+            report.timings.fetch_info = now.elapsed();
+        }
+
+        if self.no_metadata {
+            report.warnings.push(Warning::NoMetadata);
+        }
+
+        if !opts.metadata_only {
+            let now = Instant::now();
+            self.check_images(opts, &mut report.errors, &mut report.warnings)?;
+            report.timings.images = now.elapsed();
+        }
+
+        // Downgrade (or drop) the errors, per their severity.
+        for err in mem::take(&mut report.errors) {
+            match severity(&err, opts) {
+                Severity::Error => report.errors.push(err),
+                Severity::Warning => report.warnings.push(Warning::Minor(err)),
+                Severity::Ignore => (),
             }
         }
 
-        Ok(errors)
+        report.timings.find_book = self.lookup_time;
+        report.timings.total = self.lookup_time + start.elapsed();
+
+        Ok(report)
     }
 
-    fn new_from_captures(
-        client: &bedetheque::Client,
+    fn new_from_parsed(
+        provider: Option<&dyn MetadataProvider>,
         path: PathBuf,
-        captures: &regex::Captures<'_>,
+        parsed: ParsedName,
+        series: Option<&SeriesConfig>,
     ) -> Result<Self> {
-        let title = captures
-            .name("title")
-            .expect("invalid capture group for title")
-            .as_str()
-            .to_owned();
-        let volume = captures
-            .name("volume")
-            .map(|m| m.as_str().parse::<u8>().expect("valid volume"));
-        let authors = captures
-            .name("authors")
-            .expect("invalid capture group for authors")
-            .as_str()
-            .to_owned();
-        let year = captures
-            .name("year")
-            .expect("invalid capture group for year")
-            .as_str()
-            .parse::<u16>()
-            .expect("valid year");
-        let width = captures
-            .name("width")
-            .expect("invalid capture group for width")
-            .as_str()
-            .parse::<usize>()
-            .expect("valid width");
-        let url = client.find_book(&title, volume)?;
+        let ParsedName {
+            title,
+            volume,
+            authors,
+            year,
+            width,
+        } = parsed;
+        let name = get_file_name(&path)?.to_owned();
+        let lookup = Lookup {
+            file_name: &name,
+            title: &title,
+            volume,
+            series,
+        };
+        let now = Instant::now();
+        let (url, no_metadata) = match provider.map(|provider| provider.locate(&lookup)) {
+            None => (None, false),
+            Some(Ok(url)) => (Some(url), false),
+            Some(Err(err)) if err.is::<NoMetadata>() => (None, true),
+            Some(Err(err)) => return Err(err),
+        };
+        let lookup_time = now.elapsed();
+        let language = provider
+            .zip(url.as_ref())
+            .and_then(|(provider, url)| provider.language(url));
 
         Ok(Self {
             path,
+            name,
+            container: None,
             url,
+            no_metadata,
+            language,
+            title,
             authors,
+            volume,
             year,
             width,
+            extra_widths: series
+                .map(|series| series.widths.clone())
+                .unwrap_or_default(),
+            lookup_time,
         })
     }
 
+    /// Check every image of the book.
+    ///
+    fn check_images(
+        &self,
+        opts: &Options,
+        errors: &mut Vec<Error>,
+        warnings: &mut Vec<Warning>,
+    ) -> Result<()> {
+        let (mut cbz, size) = match self.open_archive() {
+            Ok(archive) => archive,
+            Err(err) => {
+                errors.push(Error::CorruptArchive(archive::corruption(err)?));
+                return Ok(());
+            }
+        };
+        // Don't spend ages on a pathological (or malicious) archive.
+        if cbz.len() > opts.max_pages {
+            errors.push(Error::TooManyPages(cbz.len()));
+            return Ok(());
+        }
+        // Pages to check, when sampling.
+        let sample = opts
+            .sample
+            .map(|n| sample::select(cbz.len(), n, opts.seed))
+            .filter(|sample| sample.len() < cbz.len());
+        if let Some(sample) = &sample {
+            warnings.push(Warning::Sampled(sample.len(), cbz.len()));
+        }
+        let mut pages = Vec::new();
+        // Files that are neither images nor tolerated.
+        let mut extra = Vec::new();
+        // Number of images, sampled or not.
+        let mut images = 0;
+        // Whether an error was found, the images are then only counted.
+        let mut failed = false;
+        // Whether a wrong date was found, it's only reported once.
+        let mut wrong_date = false;
+        // Whether every entry could be read, i.e. the images count is reliable.
+        let mut complete = true;
+
+        for i in 0..cbz.len() {
+            let mut entry = match cbz.entry(i) {
+                Ok(entry) => entry,
+                Err(err) => {
+                    errors.push(Error::CorruptArchive(archive::corruption(err)?));
+                    // Following entries can't be trusted either.
+                    complete = false;
+                    break;
+                }
+            };
+
+            if !entry.is_file {
+                continue;
+            }
+            if comicinfo::is_comicinfo(&entry.name) {
+                let mut content = Vec::new();
+                entry
+                    .reader
+                    .read_to_end(&mut content)
+                    .with_context(|| format!("failed to read {}", entry.name))?;
+                errors.extend(comicinfo::validate(&content).map(Error::ComicInfoInvalid));
+            }
+            if is_junk(&entry.name) || !is_image(&entry.name) {
+                let tolerated = !is_junk(&entry.name)
+                    && opts
+                        .extra_files
+                        .iter()
+                        .any(|pattern| pattern.matches(&entry.name));
+                if !tolerated {
+                    extra.push(entry.name);
+                }
+                continue;
+            }
+            images += 1;
+
+            // We found an error, we can stop checking here.
+            if failed {
+                continue;
+            }
+            if !wrong_date && entry.modified.is_some_and(|date| !check_date(date)) {
+                wrong_date = true;
+                // Unless requested, a wrong date hides the other errors.
+                if record(Error::Date, opts, errors) && !opts.continue_on_date_error {
+                    failed = true;
+                    continue;
+                }
+            }
+            if sample.as_ref().is_some_and(|sample| !sample.contains(&i)) {
+                continue;
+            }
+            if !self.check_image(&mut entry, opts, errors, &mut pages)? {
+                failed = true;
+            }
+        }
+
+        if complete && images < opts.min_pages {
+            errors.push(Error::TooFewPages(images));
+        }
+
+        if !extra.is_empty() {
+            errors.push(Error::ExtraFiles(extra));
+        }
+        errors.extend(self.check_archive_size(size, images, opts.size_factor));
+        check_formats(&pages, opts.allow_png_cover, errors);
+        check_bit_depths(&pages, opts.allow_png_cover, errors);
+        check_size_outliers(&pages, opts.outlier_factor, opts.allow_png_cover, errors);
+        if opts.check_subsampling {
+            check_subsampling(&pages, errors);
+        }
+        errors.extend(check_extension_case(&pages));
+        if !opts.allow_wraparound_cover {
+            check_cover_orientation(&pages, errors);
+            if opts.check_cover_spread {
+                errors.extend(self.check_cover_spread(&pages, opts.min_dual_page_margin));
+            }
+        }
+        warnings.extend(fallback_warnings(&pages));
+
+        Ok(())
+    }
+
+    /// Open the book (archive or unpacked), and return it with its size.
+    ///
+    /// Fails with `Corrupt` if the archive's index is damaged.
+    fn open_archive(&self) -> Result<(Box<dyn archive::Archive>, u64)> {
+        if self.path.is_dir() {
+            return archive::open_dir(&self.path);
+        }
+
+        let (fp, size) = self.open()?;
+        Ok((archive::open(&self.path, fp)?, size))
+    }
+
+    /// Open the archive of the book, and return it with its size.
+    ///
+    /// A book inside a collection archive is read in memory, as compressed
+    /// entries can't be seeked.
+    fn open(&self) -> Result<(Box<dyn ReadSeek>, u64)> {
+        let Some((container, entry)) = &self.container else {
+            let file = fs::File::open(&self.path).context("open error")?;
+            let size = file
+                .metadata()
+                .context("failed to read archive size")?
+                .len();
+            return Ok((Box::new(file), size));
+        };
+
+        let file = fs::File::open(container).context("open error")?;
+        let mut collection = ZipArchive::new(file).context("failed to read collection")?;
+        let mut entry = collection
+            .by_name(entry)
+            .context("book missing from its collection")?;
+        let mut bytes = Vec::new();
+        entry
+            .read_to_end(&mut bytes)
+            .context("failed to read book from its collection")?;
+        let size = bytes.len() as u64;
+
+        Ok((Box::new(Cursor::new(bytes)), size))
+    }
+
+    /// Check that the archive, of `size` bytes, isn't implausibly small for
+    /// its number of `images` and their width: it's most likely
+    /// over-compressed.
+    fn check_archive_size(&self, size: u64, images: usize, size_factor: u64) -> Option<Error> {
+        // Saturated: a huge factor only means that any archive is too small.
+        let expected = (self.width as u64)
+            .saturating_mul(images as u64)
+            .saturating_mul(size_factor);
+
+        (size < expected).then_some(Error::SuspiciouslySmall(size, expected))
+    }
+
+    /// Check that the cover isn't a double-page spread, as wide as two pages
+    /// of the book (give or take `min_margin` pixels at least).
+    fn check_cover_spread(&self, pages: &[Page], min_margin: usize) -> Option<Error> {
+        let cover = cover(pages)?;
+        let is_spread = iter::once(&self.width)
+            .chain(&self.extra_widths)
+            .any(|&expected| is_dual_page(cover.width, expected, min_margin));
+
+        is_spread.then(|| Error::CoverIsSpread(cover.name.clone()))
+    }
+
     /// Check the image.
     ///
     /// Ensure that the width of every image match the name.
@@ -148,58 +678,514 @@ impl Book {
     /// Width must be equal (single page) or more or less twice as large (dual
     /// page).
     ///
-    /// Also check the presence of EXIF metadata.
-    fn check_image(&self, entry: &mut ZipFile<'_>, errors: &mut Vec<Error>) -> Result<bool> {
+    /// Also check the presence of EXIF metadata and, if requested, the DPI.
+    fn check_image(
+        &self,
+        entry: &mut archive::Entry<'_>,
+        opts: &Options,
+        errors: &mut Vec<Error>,
+        pages: &mut Vec<Page>,
+    ) -> Result<bool> {
+        // Nothing to inspect, the format isn't supported.
+        if has_extension(&entry.name, UNSUPPORTED_EXTENSIONS) {
+            let err = Error::UnsupportedFormat(entry.name.clone());
+            return Ok(!record(err, opts, errors));
+        }
+
         let mut bytes: Vec<u8> = vec![];
-        std::io::copy(entry, &mut bytes)
-            .with_context(|| format!("failed to read image {}", entry.name()))?;
-
-        // Check width.
-        // DPR are sometimes edited, so allows 10% of variation.
-        let margin = self.width / 10;
-        let dpr_range = (2 * self.width - margin)..=(2 * self.width + margin);
-        let width = imagesize::blob_size(&bytes)
-            .with_context(|| format!("cannot get width for {}", entry.name()))?
-            .width;
-
-        if width != self.width && !dpr_range.contains(&width) {
-            errors.push(Error::Width);
+        std::io::copy(&mut entry.reader, &mut bytes)
+            .with_context(|| format!("failed to read image {}", entry.name))?;
+
+        // Check size: a tiny image is most likely a failed export.
+        if (bytes.len() as u64) < opts.min_image_size
+            && record(
+                Error::TinyImage(entry.name.clone(), bytes.len()),
+                opts,
+                errors,
+            )
+        {
             return Ok(false);
         }
 
-        // Check EXIF.
-        let mut reader = BufReader::new(Cursor::new(&*bytes));
-        let exifreader = exif::Reader::new();
-        match exifreader.read_from_container(&mut reader) {
-            Ok(_) => {
-                errors.push(Error::Exif);
-                Ok(false)
+        let picture = picture::inspect(&bytes)
+            .with_context(|| format!("cannot inspect image {}", entry.name))?;
+        pages.push(Page {
+            name: entry.name.clone(),
+            format: picture.format,
+            bit_depth: picture.bit_depth,
+            subsampling: jpeg::subsampling(&bytes),
+            fallback: picture
+                .fallback
+                .map(|format| format!("{format:?}").to_lowercase()),
+            width: picture.width,
+            height: picture.height,
+            size: bytes.len(),
+        });
+
+        // Checks below the error severity don't stop the following ones.
+        for err in self.image_errors(&entry.name, &bytes, &picture, opts) {
+            if record(err, opts, errors) {
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Return the errors of the image `name`, in the order they are checked.
+    fn image_errors(
+        &self,
+        name: &str,
+        bytes: &[u8],
+        picture: &Picture,
+        opts: &Options,
+    ) -> Vec<Error> {
+        let mut errors = Vec::new();
+
+        // Check width, against every accepted width.
+        let width = picture.width;
+        let valid_width = iter::once(&self.width)
+            .chain(&self.extra_widths)
+            .any(|&expected| {
+                width == expected || is_dual_page(width, expected, opts.min_dual_page_margin)
+            });
+        if !valid_width {
+            errors.push(Error::Width);
+        }
+
+        // Check height, against the ladder if any: resizing may be off by 1%.
+        let on_ladder = opts
+            .height_ladder
+            .iter()
+            .any(|&expected| picture.height.abs_diff(expected) <= expected / 100);
+        if !opts.height_ladder.is_empty() && !on_ladder {
+            errors.push(Error::Height(name.to_owned(), picture.height));
+        }
+
+        // Check that dimensions are even, if requested.
+        if opts.check_even_dimensions
+            && (!picture.width.is_multiple_of(2) || !picture.height.is_multiple_of(2))
+        {
+            errors.push(Error::OddDimension(
+                name.to_owned(),
+                picture.width,
+                picture.height,
+            ));
+        }
+
+        if picture.animated {
+            errors.push(Error::AnimatedImage(name.to_owned()));
+        }
+        errors.extend(check_jpeg(name, bytes, opts));
+        errors.extend(check_trailing_data(name, bytes, picture.format, opts));
+
+        // Check DPI, when declared.
+        if let Some(page_width) = opts.page_width {
+            let dpi = picture
+                .exif
+                .as_ref()
+                .and_then(exif_dpi)
+                .or_else(|| jpeg::jfif_dpi(bytes));
+            if let Some(dpi) = dpi {
+                // Expected DPI, from the declared width and the physical width.
+                #[allow(clippy::cast_precision_loss)] // Widths are way below 2^52.
+                let expected = self.width as f64 * 2.54 / page_width;
+                if (dpi - expected).abs() > expected / 10.0 {
+                    errors.push(Error::Dpi(name.to_owned(), dpi));
+                }
             }
-            Err(exif::Error::NotFound(_)) => Ok(true),
-            Err(err) => Err(err).with_context(|| format!("cannot check EXIF for {}", entry.name())),
         }
+
+        // Check orientation, if requested: pixels should already be upright.
+        if opts.check_orientation {
+            let orientation = picture
+                .exif
+                .as_ref()
+                .and_then(|exif| exif.get_field(exif::Tag::Orientation, exif::In::PRIMARY))
+                .and_then(|field| field.value.get_uint(0));
+            // 1 is the normal orientation, and the default.
+            if orientation.is_some_and(|orientation| orientation != 1) {
+                errors.push(Error::Orientation(name.to_owned()));
+            }
+        }
+
+        // Check for an embedded thumbnail, if requested: it's reported even if
+        // its tags are tolerated.
+        let has_thumbnail = picture.exif.as_ref().is_some_and(|exif| {
+            exif.fields()
+                .any(|field| field.ifd_num == exif::In::THUMBNAIL)
+        });
+        if opts.check_thumbnail && has_thumbnail {
+            errors.push(Error::EmbeddedThumbnail(name.to_owned()));
+        }
+
+        // Check EXIF, harmless tags are tolerated.
+        let has_exif = picture.exif.as_ref().is_some_and(|exif| {
+            exif.fields()
+                .any(|field| !opts.exif_allowlist.contains(&field.tag.to_string()))
+        });
+        if has_exif {
+            errors.push(Error::Exif);
+        }
+
+        errors
     }
 
     /// Check the book's metadata (authors, publication years, ...)
+    ///
+    /// Return the URL of the book's series, if found.
     fn check_book_metadata(
         &self,
-        client: &bedetheque::Client,
-        errors: &mut Vec<Error>,
-    ) -> Result<()> {
-        let info = client
-            .fetch_info(&self.url)
-            .context("failed to get metadata from bedetheque")?;
+        provider: &dyn MetadataProvider,
+        url: &Url,
+        opts: &Options,
+        report: &mut Report,
+    ) -> Result<Option<Url>> {
+        let info = provider.metadata(url).context("failed to get metadata")?;
+        let aliases = &opts.aliases;
 
-        if normalize(&info.authors) != normalize(&self.authors) {
-            errors.push(Error::Authors(info.authors));
+        if opts.check_filename {
+            report.errors.extend(self.check_filename(&info, opts));
         }
 
-        if !info.years.contains(&self.year) {
-            errors.push(Error::Year(info.years));
+        let known = &info.names;
+        let expected = authors::normalize(&info.authors, known, aliases);
+        let found = authors::normalize(&self.authors, known, aliases);
+        if authors::same_names(&expected, &found) {
+            // Same authors, listed in another order.
+            if expected != found && opts.author_order == AuthorOrder::Strict {
+                report.errors.push(Error::AuthorOrder(info.authors));
+            }
+        } else {
+            let similarity = authors::similarity(&info.authors, &self.authors, known, aliases);
+            // Fuzzy matching is opt-in: by default, authors must match exactly.
+            if opts.author_threshold >= 100.0 || similarity < opts.author_threshold {
+                report.errors.push(Error::Authors(info.authors, similarity));
+            }
         }
 
-        Ok(())
+        // The URL may come from a stale cache, or an ambiguous search.
+        if let (Some(expected), Some(found)) = (self.volume, info.volume) {
+            if expected != found {
+                report.errors.push(Error::VolumeMismatch(found));
+            }
+        }
+
+        if info.years.is_empty() {
+            // Nothing to check against.
+            report.warnings.push(Warning::NoYear);
+        } else if !info
+            .years
+            .iter()
+            .any(|year| year.abs_diff(self.year) <= opts.year_tolerance)
+        {
+            report.errors.push(Error::Year(info.years));
+        } else if let (YearPolicy::First, Some(&first)) = (opts.year_policy, info.years.first()) {
+            if first.abs_diff(self.year) > opts.year_tolerance {
+                report.errors.push(Error::FirstEditionYear(first));
+            }
+        }
+
+        Ok(info.series)
+    }
+
+    /// Check that the file name is the one derived from the metadata: title,
+    /// volume, authors and year.
+    fn check_filename(&self, info: &VolumeInfo, opts: &Options) -> Option<Error> {
+        let title = info.series_title.as_deref().map_or_else(
+            || self.title.clone(),
+            |series_title| bedetheque::canonical_title(series_title, &self.title),
+        );
+        // One-shots have no volume, whatever bedetheque says.
+        let volume = self.volume.map(|volume| info.volume.unwrap_or(volume));
+        // The file's year is kept if it's the one of an edition.
+        let year = match opts.year_policy {
+            YearPolicy::Any if info.years.contains(&self.year) => self.year,
+            _ => info.years.first().copied().unwrap_or(self.year),
+        };
+        // Not the container's name, if any.
+        let name = self.name.rsplit('/').next().unwrap_or(&self.name);
+        // As read by `Book::new`.
+        let name = if opts.underscores_as_spaces {
+            name.replace('_', " ")
+        } else {
+            name.to_owned()
+        };
+
+        let expected = filename::canonical_name(&name, &title, volume, &info.authors, year)?;
+        (expected != name).then_some(Error::FilenameMismatch(expected))
+    }
+}
+
+/// Check if the archive entry `name` is an image, from its extension.
+pub(crate) fn is_image(name: &str) -> bool {
+    has_extension(name, IMAGE_EXTENSIONS)
+}
+
+/// Check if the archive entry `name` has one of the (lowercase) `extensions`.
+fn has_extension(name: &str, extensions: &[&str]) -> bool {
+    Path::new(name)
+        .extension()
+        .and_then(OsStr::to_str)
+        .is_some_and(|extension| extensions.contains(&extension.to_lowercase().as_str()))
+}
+
+/// Check if the archive entry `name` was left by an operating system (e.g.
+/// macOS resource forks).
+pub(crate) fn is_junk(name: &str) -> bool {
+    let file_name = name.rsplit('/').next().unwrap_or(name);
+
+    name.starts_with("__MACOSX/") || file_name.starts_with("._") || JUNK_FILES.contains(&file_name)
+}
+
+/// Return the cover of the book, i.e. its first page in name order.
+fn cover(pages: &[Page]) -> Option<&Page> {
+    pages.iter().min_by(|a, b| a.name.cmp(&b.name))
+}
+
+/// Check if `width` is the width of a dual page, for single pages `expected`
+/// pixels wide.
+///
+/// The margin is at least `min_margin` pixels, for narrow pages.
+fn is_dual_page(width: usize, expected: usize, min_margin: usize) -> bool {
+    // DPR are sometimes edited, so allows 10% of variation.
+    let margin = (expected / 10).max(min_margin);
+    let dpr_range = (2 * expected).saturating_sub(margin)..=(2 * expected + margin);
+
+    dpr_range.contains(&width)
+}
+
+/// Check that the cover is in portrait orientation: a landscape cover is most
+/// likely a misfiled spread.
+///
+/// Nearly square covers are tolerated (5% margin).
+fn check_cover_orientation(pages: &[Page], errors: &mut Vec<Error>) {
+    if let Some(cover) = cover(pages) {
+        #[allow(clippy::cast_precision_loss)] // Dimensions are way below 2^52.
+        let is_landscape = cover.width as f64 > cover.height as f64 * 1.05;
+        if is_landscape {
+            errors.push(Error::CoverOrientation(cover.name.clone()));
+        }
+    }
+}
+
+/// Check that every page uses the same image format.
+///
+/// If `allow_png_cover` is set, a PNG cover is accepted among JPEG pages.
+fn check_formats(pages: &[Page], allow_png_cover: bool, errors: &mut Vec<Error>) {
+    let pages = without_png_cover(pages, allow_png_cover);
+
+    let unexpected = minority(&pages, |page| page.format);
+    if !unexpected.is_empty() {
+        errors.push(Error::MixedFormats(unexpected));
+    }
+}
+
+/// Check that every page has the same bit depth, except a PNG cover if
+/// allowed.
+fn check_bit_depths(pages: &[Page], allow_png_cover: bool, errors: &mut Vec<Error>) {
+    let pages = without_png_cover(pages, allow_png_cover);
+
+    let unexpected = minority(&pages, |page| page.bit_depth);
+    if !unexpected.is_empty() {
+        errors.push(Error::MixedBitDepth(unexpected));
+    }
+}
+
+/// Return the pages, except the cover if it's a PNG and that's allowed.
+fn without_png_cover(pages: &[Page], allow_png_cover: bool) -> Vec<&Page> {
+    let cover = cover(pages);
+
+    pages
+        .iter()
+        .filter(|page| {
+            let is_cover = cover.is_some_and(|cover| cover.name == page.name);
+            !(allow_png_cover && is_cover && page.format == Some(ImageType::Png))
+        })
+        .collect()
+}
+
+/// Check that no page is more than `factor` times larger than the median page,
+/// except a PNG cover if allowed (much larger than JPEG pages).
+///
+/// A few pages are needed for the median to be meaningful.
+fn check_size_outliers(
+    pages: &[Page],
+    factor: usize,
+    allow_png_cover: bool,
+    errors: &mut Vec<Error>,
+) {
+    let pages = without_png_cover(pages, allow_png_cover);
+    if pages.len() < 3 {
+        return;
+    }
+
+    let mut sizes = pages.iter().map(|page| page.size).collect::<Vec<_>>();
+    sizes.sort_unstable();
+    let median = sizes[sizes.len() / 2];
+
+    errors.extend(
+        pages
+            .iter()
+            .filter(|page| page.size > median.saturating_mul(factor))
+            .map(|page| Error::SizeOutlier(page.name.clone(), page.size, median)),
+    );
+}
+
+/// Return the severity of an error: the configured one, or the default one
+/// (minor issues are warnings).
+///
+/// In strict mode, warnings are errors.
+fn severity(err: &Error, opts: &Options) -> Severity {
+    let severity = opts
+        .severities
+        .get(err.check())
+        .copied()
+        .unwrap_or(if err.is_minor() {
+            Severity::Warning
+        } else {
+            Severity::Error
+        });
+
+    if opts.strict && severity == Severity::Warning {
+        Severity::Error
+    } else {
+        severity
+    }
+}
+
+/// Record `err`, unless its check is ignored or was already reported for the
+/// book.
+///
+/// Return true if `err` fails the book: the following checks can then be
+/// skipped, whereas they must still run after a mere warning.
+fn record(err: Error, opts: &Options, errors: &mut Vec<Error>) -> bool {
+    let severity = severity(&err, opts);
+    if severity == Severity::Ignore {
+        return false;
     }
+    if !errors.iter().any(|known| known.check() == err.check()) {
+        errors.push(err);
+    }
+
+    severity == Severity::Error
+}
+
+/// Check the JPEG-specific properties of the image `name`, as requested:
+/// encoding, color space and quality.
+fn check_jpeg(name: &str, bytes: &[u8], opts: &Options) -> Vec<Error> {
+    let mut errors = Vec::new();
+
+    // Check JPEG encoding, if requested.
+    if let Some(required) = opts.jpeg_encoding {
+        match jpeg::encoding(bytes) {
+            Some(jpeg::Encoding::Progressive) if required == jpeg::Encoding::Baseline => {
+                errors.push(Error::ProgressiveJpeg(name.to_owned()));
+            }
+            Some(jpeg::Encoding::Baseline) if required == jpeg::Encoding::Progressive => {
+                errors.push(Error::BaselineJpeg(name.to_owned()));
+            }
+            _ => (),
+        }
+    }
+
+    // Check JPEG color space, if requested.
+    if opts.check_cmyk && jpeg::is_cmyk(bytes) {
+        errors.push(Error::CmykImage(name.to_owned()));
+    }
+
+    // Check JPEG quality, if requested.
+    if let Some(min_quality) = opts.min_quality {
+        errors.extend(
+            jpeg::estimate_quality(bytes)
+                .filter(|&quality| quality < min_quality)
+                .map(|quality| Error::LowQuality(name.to_owned(), quality)),
+        );
+    }
+
+    errors
+}
+
+/// Check that no data follows the image data of `name`, if requested: a few
+/// bytes of padding are tolerated.
+fn check_trailing_data(
+    name: &str,
+    bytes: &[u8],
+    format: Option<ImageType>,
+    opts: &Options,
+) -> Option<Error> {
+    if !opts.check_trailing_data {
+        return None;
+    }
+
+    picture::trailing_bytes(bytes, format?)
+        .filter(|&extra| extra > MAX_PADDING)
+        .map(|extra| Error::TrailingGarbage(name.to_owned(), extra))
+}
+
+/// Check that every color JPEG page uses the same chroma subsampling.
+fn check_subsampling(pages: &[Page], errors: &mut Vec<Error>) {
+    let pages = pages
+        .iter()
+        .filter(|page| page.subsampling.is_some())
+        .collect::<Vec<_>>();
+
+    let unexpected = minority(&pages, |page| page.subsampling.clone());
+    if !unexpected.is_empty() {
+        errors.push(Error::MixedSubsampling(unexpected));
+    }
+}
+
+/// Report the formats, and their number of pages, that required the fallback
+/// decoder to get the dimensions.
+fn fallback_warnings(pages: &[Page]) -> impl Iterator<Item = Warning> {
+    let mut counts = BTreeMap::new();
+    for format in pages.iter().filter_map(|page| page.fallback.clone()) {
+        *counts.entry(format).or_insert(0_usize) += 1;
+    }
+
+    counts
+        .into_iter()
+        .map(|(format, count)| Warning::Fallback(format, count))
+}
+
+/// Check that every page's extension uses the same case (e.g. no `.JPG` among
+/// `.jpg`).
+fn check_extension_case(pages: &[Page]) -> Option<Error> {
+    let pages = pages.iter().collect::<Vec<_>>();
+
+    let unexpected = minority(&pages, |page| {
+        let extension = Path::new(&page.name).extension()?.to_str()?;
+        Some(if extension == extension.to_lowercase() {
+            "lowercase"
+        } else if extension == extension.to_uppercase() {
+            "uppercase"
+        } else {
+            "mixed"
+        })
+    });
+    (!unexpected.is_empty()).then_some(Error::InconsistentExtensionCase(unexpected))
+}
+
+/// Return the (sorted) names of the pages whose `key` differs from the most
+/// common one.
+fn minority<K: Ord>(pages: &[&Page], key: impl Fn(&Page) -> K) -> Vec<String> {
+    let mut counts = BTreeMap::new();
+    for page in pages {
+        *counts.entry(key(page)).or_insert(0_usize) += 1;
+    }
+    let Some((main_key, _)) = counts.into_iter().max_by_key(|&(_, count)| count) else {
+        return Vec::new();
+    };
+
+    let mut names = pages
+        .iter()
+        .filter(|page| key(page) != main_key)
+        .map(|page| page.name.clone())
+        .collect::<Vec<_>>();
+    names.sort();
+
+    names
 }
 
 /// Check that the date match the expected one.
@@ -210,23 +1196,31 @@ fn check_date(date: DateTime) -> bool {
         && EXPECTED_DATE.day() == date.day()
 }
 
+/// Return the horizontal resolution (in dots per inch) declared in EXIF, if
+/// any.
+fn exif_dpi(exif: &exif::Exif) -> Option<f64> {
+    let field = exif.get_field(exif::Tag::XResolution, exif::In::PRIMARY)?;
+    let resolution = match &field.value {
+        exif::Value::Rational(values) => values.first()?.to_f64(),
+        _ => return None,
+    };
+    // Resolution is in inches by default.
+    let unit = exif
+        .get_field(exif::Tag::ResolutionUnit, exif::In::PRIMARY)
+        .and_then(|field| field.value.get_uint(0))
+        .unwrap_or(2);
+
+    match unit {
+        2 => Some(resolution),
+        3 => Some(resolution * 2.54),
+        _ => None,
+    }
+}
+
 /// Extract the file name, as UTF-8 string, from a file path.
-fn get_file_name(path: &Path) -> &str {
+fn get_file_name(path: &Path) -> Result<&str> {
     path.file_name()
-        .expect("filename")
+        .context("no file name")?
         .to_str()
-        .expect("valid UTF-8")
-}
-
-/// Normalize authors list for easier comparison, best effort...
-fn normalize(authors: &str) -> String {
-    authors
-        // Case insensitive.
-        .to_lowercase()
-        // Romanization mismatch.
-        .replace(['ā', 'â'], "aa")
-        .replace(['ū', 'û'], "uu")
-        .replace(['ē', 'ê'], "ee")
-        .replace(['ō', 'ô'], "ou")
-        .replace("oo", "ou")
+        .context("file name is not valid UTF-8")
 }
@@ -0,0 +1,609 @@
+//! Command-line arguments parsing.
+
+use crate::{
+    authors::Aliases,
+    bedetheque::{Language, SearchField},
+    config::{Config, Severity},
+    glob::Pattern,
+    jpeg,
+    metadata::AuthorLabels,
+    sort::Order,
+    tape::Tape,
+    template::Template,
+};
+use anyhow::{bail, Context, Error, Result};
+use std::{collections::HashMap, env, path::PathBuf, str::FromStr};
+
+/// Output format of the results.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub(crate) enum Format {
+    /// Human-readable text, with colors.
+    #[default]
+    Human,
+    /// JSON document.
+    Json,
+    /// CSV, one row per book.
+    Csv,
+    /// JSON, one line per book, printed as soon as the book is checked.
+    Ndjson,
+    /// Aligned table, one row per book, fitting in the terminal.
+    Table,
+}
+
+impl FromStr for Format {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "human" => Self::Human,
+            "json" => Self::Json,
+            "csv" => Self::Csv,
+            "ndjson" => Self::Ndjson,
+            "table" => Self::Table,
+            _ => bail!("expected `human`, `json`, `csv`, `ndjson` or `table`"),
+        })
+    }
+}
+
+/// Which edition's year the file name must use.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub(crate) enum YearPolicy {
+    /// The year of any edition.
+    #[default]
+    Any,
+    /// The year of the first edition.
+    First,
+}
+
+impl FromStr for YearPolicy {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "any" => Self::Any,
+            "first" => Self::First,
+            _ => bail!("expected `first` or `any`"),
+        })
+    }
+}
+
+/// Whether the authors of the file name must be in the same order as on
+/// bedetheque (writers first, then pencillers).
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub(crate) enum AuthorOrder {
+    /// Any order.
+    #[default]
+    Any,
+    /// The same order.
+    Strict,
+}
+
+impl FromStr for AuthorOrder {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "any" => Self::Any,
+            "strict" => Self::Strict,
+            _ => bail!("expected `strict` or `any`"),
+        })
+    }
+}
+
+/// Default maximum number of entries in an archive.
+const DEFAULT_MAX_PAGES: usize = 2000;
+
+/// Default minimum number of pages in a book.
+const DEFAULT_MIN_PAGES: usize = 5;
+
+/// Prefix of the environment variables setting the options.
+const ENV_PREFIX: &str = "CBZLINT_";
+
+/// Options without value that can be set from the environment.
+const ENV_SWITCHES: &[&str] = &[
+    "--timings",
+    "--json",
+    "--explain",
+    "--metadata-only",
+    "--images-only",
+    "--underscores-as-spaces",
+    "--strict",
+    "--check-series",
+    "--check-orientation",
+    "--check-even-dimensions",
+    "--check-subsampling",
+    "--check-cmyk",
+    "--check-cover-spread",
+    "--check-filename",
+    "--check-trailing-data",
+    "--continue-on-date-error",
+    "--dump-matches",
+    "--recursive",
+    "--watch",
+    "--recursive-summary",
+    "--allow-png-cover",
+    "--allow-wraparound-cover",
+    "--no-reuse-cache",
+    "--resume",
+    "--loose",
+];
+
+/// Options with a value that can be set from the environment.
+const ENV_VALUES: &[&str] = &[
+    "--config",
+    "--format",
+    "--sort",
+    "--template",
+    "--cache",
+    "--page-width",
+    "--height-ladder",
+    "--require",
+    "--max-pages",
+    "--min-pages",
+    "--min-quality",
+    "--sample",
+    "--seed",
+    "--save-metadata",
+    "--summary-json",
+    "--metadata-from",
+    "--metadata-csv",
+    "--languages",
+    "--search-by",
+    "--author-threshold",
+    "--author-order",
+    "--year-policy",
+    "--year-tolerance",
+    "--max-requests",
+    "--on-failure",
+    "--record",
+    "--replay",
+];
+
+/// Command-line options.
+#[derive(Debug, Default)]
+#[allow(clippy::struct_excessive_bools)] // Flags are, well, booleans.
+pub(crate) struct Options {
+    /// Write a default configuration file instead of checking books.
+    pub(crate) init: bool,
+    /// Overwrite the existing configuration file on `--init`.
+    pub(crate) force: bool,
+    /// Diagnose the environment instead of checking books.
+    pub(crate) doctor: bool,
+    /// Configuration file to use, instead of the default one.
+    pub(crate) config_path: Option<PathBuf>,
+    /// Directories to compare, instead of checking books.
+    pub(crate) compare: Option<(PathBuf, PathBuf)>,
+    /// Text whose normalization to preview, instead of checking books.
+    pub(crate) normalize_preview: Option<String>,
+    /// Files or directories to check.
+    pub(crate) paths: Vec<PathBuf>,
+    /// Check the directories as unpacked books.
+    pub(crate) loose: bool,
+    /// Only check the files whose name matches one of these patterns, if any.
+    pub(crate) only: Vec<Pattern>,
+    /// Report the time spent in each step of the check.
+    pub(crate) timings: bool,
+    /// Output format of the results.
+    pub(crate) format: Format,
+    /// Template of the line reported for each book, if any.
+    pub(crate) template: Option<Template>,
+    /// Order in which the books are reported.
+    pub(crate) sort: Order,
+    /// Explain why each error was reported.
+    pub(crate) explain: bool,
+    /// Only check the metadata, skip the images.
+    pub(crate) metadata_only: bool,
+    /// Only check the images, without any network access.
+    pub(crate) offline: bool,
+    /// Explore the directories recursively.
+    pub(crate) recursive: bool,
+    /// Check the books as they change, instead of once.
+    pub(crate) watch: bool,
+    /// Group the results by directory, with a tally for each.
+    pub(crate) recursive_summary: bool,
+    /// Read underscores as spaces in the file names.
+    pub(crate) underscores_as_spaces: bool,
+    /// Report minor issues as errors, instead of warnings.
+    pub(crate) strict: bool,
+    /// Report the bedetheque page matched by each book, once every book is
+    /// checked.
+    pub(crate) dump_matches: bool,
+    /// Check that no volume is missing from the series.
+    pub(crate) check_series: bool,
+    /// Physical width of a page, in cm, to check the images' DPI.
+    pub(crate) page_width: Option<f64>,
+    /// Accepted heights of the images, in pixels, if restricted.
+    pub(crate) height_ladder: Vec<usize>,
+    /// Required encoding of the JPEG images, if any.
+    pub(crate) jpeg_encoding: Option<jpeg::Encoding>,
+    /// Accept a PNG cover among JPEG pages.
+    pub(crate) allow_png_cover: bool,
+    /// Accept a landscape (wraparound) cover.
+    pub(crate) allow_wraparound_cover: bool,
+    /// Check that the images' dimensions are even.
+    pub(crate) check_even_dimensions: bool,
+    /// Check that the cover isn't a double-page spread.
+    pub(crate) check_cover_spread: bool,
+    /// Check that the file names are the ones derived from the metadata.
+    pub(crate) check_filename: bool,
+    /// Check that no data follows the images' data.
+    pub(crate) check_trailing_data: bool,
+    /// Check that the JPEG images use the same chroma subsampling.
+    pub(crate) check_subsampling: bool,
+    /// Check that the images don't rely on the EXIF orientation.
+    pub(crate) check_orientation: bool,
+    /// Check that the JPEG images don't use the CMYK color space.
+    pub(crate) check_cmyk: bool,
+    /// Keep checking the images after a wrong date.
+    pub(crate) continue_on_date_error: bool,
+    /// Maximum number of entries in an archive, beyond it's not checked.
+    pub(crate) max_pages: usize,
+    /// Minimum number of pages in a book, below it's most likely truncated.
+    pub(crate) min_pages: usize,
+    /// Only check this many pages at the start, the end and in between, if
+    /// set.
+    pub(crate) sample: Option<usize>,
+    /// Seed of the pages sampling.
+    pub(crate) seed: u64,
+    /// Minimum estimated quality of the JPEG images, if any.
+    pub(crate) min_quality: Option<f64>,
+    /// Where to save the metadata snapshot, if any.
+    pub(crate) save_metadata: Option<PathBuf>,
+    /// Where to save the aggregate statistics of the run, if any.
+    pub(crate) summary_json: Option<PathBuf>,
+    /// Metadata snapshot to check against, instead of bedetheque.
+    pub(crate) metadata_from: Option<PathBuf>,
+    /// Catalog (CSV) of the expected metadata to check against, instead of
+    /// bedetheque.
+    pub(crate) metadata_csv: Option<PathBuf>,
+    /// Languages in which to search the books, in order.
+    pub(crate) languages: Vec<Language>,
+    /// Search field tried first on bedetheque.
+    pub(crate) search_field: SearchField,
+    /// Maximum number of requests to send to bedetheque, if any.
+    pub(crate) max_requests: Option<u64>,
+    /// Reuse the previous lookups (this run's, or the persistent cache's).
+    pub(crate) reuse_cache: bool,
+    /// Persistent cache of the bedetheque lookups, if any.
+    pub(crate) cache: Option<PathBuf>,
+    /// Skip the books that passed in the previous run, per the journal kept
+    /// next to the cache.
+    pub(crate) resume: bool,
+    /// Command to run on each book that fails, if any.
+    pub(crate) on_failure: Option<String>,
+    /// Where to record (or replay) the bedetheque responses, if any.
+    pub(crate) tape: Option<Tape>,
+    /// Which edition's year the file names must use.
+    pub(crate) year_policy: YearPolicy,
+    /// Accepted difference, in years, with the editions' years.
+    pub(crate) year_tolerance: u16,
+    /// Minimum similarity (from 0 to 100) of matching authors lists.
+    pub(crate) author_threshold: f64,
+    /// Whether the authors must be in the same order as on bedetheque.
+    pub(crate) author_order: AuthorOrder,
+    /// Authors aliases, from the configuration file.
+    pub(crate) aliases: Aliases,
+    /// Labels of the authors fields on bedetheque, from the configuration
+    /// file.
+    pub(crate) author_labels: AuthorLabels,
+    /// Minimum size of an image, in bytes, from the configuration file.
+    pub(crate) min_image_size: u64,
+    /// Minimum size of an archive, in bytes per pixel of width and per page,
+    /// from the configuration file.
+    pub(crate) size_factor: u64,
+    /// Largest size of a page, as a multiple of the median page size, from
+    /// the configuration file.
+    pub(crate) outlier_factor: usize,
+    /// Smallest margin of the dual pages' width, in pixels, from the
+    /// configuration file.
+    pub(crate) min_dual_page_margin: usize,
+    /// EXIF tags tolerated in the images, from the configuration file.
+    pub(crate) exif_allowlist: Vec<String>,
+    /// Report the images embedding a thumbnail, from the configuration file.
+    pub(crate) check_thumbnail: bool,
+    /// Severity of the checks, by name, from the configuration file.
+    pub(crate) severities: HashMap<String, Severity>,
+    /// Files tolerated alongside the images, from the configuration file.
+    pub(crate) extra_files: Vec<Pattern>,
+}
+
+impl Options {
+    /// Parse the options from the command-line arguments.
+    pub(crate) fn parse() -> Result<Self> {
+        Self::parse_from(env::args().skip(1)) // Skip the binary name.
+    }
+
+    /// Parse the options from `args`, as given on the command line (without
+    /// the binary name).
+    pub(crate) fn parse_from(args: impl IntoIterator<Item = String>) -> Result<Self> {
+        let mut opts = Self {
+            author_threshold: 100.0,
+            max_pages: DEFAULT_MAX_PAGES,
+            min_pages: DEFAULT_MIN_PAGES,
+            reuse_cache: true,
+            ..Self::default()
+        };
+        // Options from the environment come first, to be overridden by the
+        // command line.
+        let mut args = env_args()?.into_iter().chain(args);
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--init" => opts.init = true,
+                "--force" => opts.force = true,
+                "--doctor" => opts.doctor = true,
+                "--compare" => {
+                    let a = value(&mut args, &arg)?;
+                    let b = value(&mut args, &arg)?;
+                    opts.compare = Some((a, b));
+                }
+                "--normalize-preview" => opts.normalize_preview = Some(value(&mut args, &arg)?),
+                "--config" => opts.config_path = Some(value(&mut args, &arg)?),
+                "--timings" => opts.timings = true,
+                "--json" => opts.format = Format::Json,
+                "--format" => opts.format = value(&mut args, &arg)?,
+                "--sort" => opts.sort = value(&mut args, &arg)?,
+                "--template" => opts.template = Some(value(&mut args, &arg)?),
+                "--explain" => opts.explain = true,
+                "--metadata-only" | "--check-url-only" => opts.metadata_only = true,
+                "--images-only" | "--no-network" => opts.offline = true,
+                "--underscores-as-spaces" => opts.underscores_as_spaces = true,
+                "--strict" => opts.strict = true,
+                "--cache" => opts.cache = Some(value(&mut args, &arg)?),
+                "--no-reuse-cache" => opts.reuse_cache = false,
+                "--resume" => opts.resume = true,
+                "--check-series" => opts.check_series = true,
+                "--dump-matches" => opts.dump_matches = true,
+                "--recursive" | "-r" => opts.recursive = true,
+                "--watch" => opts.watch = true,
+                "--recursive-summary" => {
+                    opts.recursive = true;
+                    opts.recursive_summary = true;
+                }
+                "--page-width" => opts.page_width = Some(value(&mut args, &arg)?),
+                "--height-ladder" => {
+                    opts.height_ladder = heights(&value::<String>(&mut args, &arg)?)?;
+                }
+                "--require" => opts.jpeg_encoding = Some(value(&mut args, &arg)?),
+                "--allow-png-cover" => opts.allow_png_cover = true,
+                "--allow-wraparound-cover" => opts.allow_wraparound_cover = true,
+                "--check-orientation" => opts.check_orientation = true,
+                "--check-even-dimensions" => opts.check_even_dimensions = true,
+                "--check-cover-spread" => opts.check_cover_spread = true,
+                "--check-filename" => opts.check_filename = true,
+                "--check-trailing-data" => opts.check_trailing_data = true,
+                "--loose" => opts.loose = true,
+                "--check-subsampling" => opts.check_subsampling = true,
+                "--check-cmyk" => opts.check_cmyk = true,
+                "--continue-on-date-error" => opts.continue_on_date_error = true,
+                "--max-pages" => opts.max_pages = value(&mut args, &arg)?,
+                "--min-pages" => opts.min_pages = value(&mut args, &arg)?,
+                "--sample" => opts.sample = Some(value(&mut args, &arg)?),
+                "--seed" => opts.seed = value(&mut args, &arg)?,
+                "--min-quality" => opts.min_quality = Some(value(&mut args, &arg)?),
+                "--save-metadata" => opts.save_metadata = Some(value(&mut args, &arg)?),
+                "--summary-json" => opts.summary_json = Some(value(&mut args, &arg)?),
+                "--metadata-from" => opts.metadata_from = Some(value(&mut args, &arg)?),
+                "--metadata-csv" => opts.metadata_csv = Some(value(&mut args, &arg)?),
+                "--languages" => opts.languages = languages(&value::<String>(&mut args, &arg)?)?,
+                "--search-by" => opts.search_field = value(&mut args, &arg)?,
+                "--author-threshold" => {
+                    opts.author_threshold = value(&mut args, &arg)?;
+                }
+                "--author-order" => opts.author_order = value(&mut args, &arg)?,
+                "--year-policy" => opts.year_policy = value(&mut args, &arg)?,
+                "--year-tolerance" => opts.year_tolerance = value(&mut args, &arg)?,
+                "--max-requests" => opts.max_requests = Some(value(&mut args, &arg)?),
+                "--on-failure" => opts.on_failure = Some(value(&mut args, &arg)?),
+                "--only" => opts
+                    .only
+                    .push(Pattern::new(&value::<String>(&mut args, &arg)?)),
+                "--record" => opts.tape = Some(Tape::Record(value(&mut args, &arg)?)),
+                "--replay" => opts.tape = Some(Tape::Replay(value(&mut args, &arg)?)),
+                flag if flag.starts_with("--") => bail!("unknown option `{flag}`"),
+                path => opts.paths.push(PathBuf::from(path)),
+            }
+        }
+
+        opts.fill_defaults()?;
+        if opts.init {
+            // The configuration file doesn't exist yet, or will be replaced.
+            return Ok(opts);
+        }
+        if opts.force {
+            bail!("--force can only be used with --init");
+        }
+        if opts.doctor {
+            // The configuration file is diagnosed, not loaded.
+            return Ok(opts);
+        }
+
+        opts.apply(Config::load(opts.config_path.as_deref())?);
+        opts.validate()?;
+
+        Ok(opts)
+    }
+
+    /// Set the options left unset by the command line.
+    fn fill_defaults(&mut self) -> Result<()> {
+        // Patterns accumulate, so the environment's is only used when none is
+        // given on the command line, instead of being prepended as an argument.
+        if self.only.is_empty() {
+            if let Some(pattern) = env_value("--only")? {
+                self.only.push(Pattern::new(&pattern));
+            }
+        }
+        if self.languages.is_empty() {
+            self.languages.push(Language::French);
+        }
+
+        Ok(())
+    }
+
+    /// Apply the settings of the configuration file.
+    fn apply(&mut self, config: Config) {
+        self.aliases = Aliases::new(&config.aliases);
+        self.author_labels =
+            AuthorLabels::new(&config.metadata.writers, &config.metadata.pencillers);
+        self.min_image_size = config.images.min_size;
+        self.size_factor = config.images.size_factor;
+        self.outlier_factor = config.images.outlier_factor;
+        self.min_dual_page_margin = config.images.min_dual_page_margin;
+        self.exif_allowlist = config.exif.allow;
+        self.check_thumbnail = config.exif.check_thumbnail;
+        self.severities = config.severity;
+        self.extra_files = config
+            .archive
+            .allow
+            .iter()
+            .map(|pattern| Pattern::new(pattern))
+            .collect();
+    }
+
+    /// Check if the metadata come from a local file (snapshot or catalog),
+    /// instead of bedetheque.
+    pub(crate) fn local_metadata(&self) -> bool {
+        self.metadata_from.is_some() || self.metadata_csv.is_some()
+    }
+
+    /// Reject the incompatible or invalid options.
+    fn validate(&self) -> Result<()> {
+        if (self.metadata_only || self.check_series) && self.offline {
+            bail!("cannot check metadata without network access");
+        }
+        if self.metadata_from.is_some() && self.metadata_csv.is_some() {
+            bail!("--metadata-from cannot be used with --metadata-csv");
+        }
+        if self.save_metadata.is_some() && (self.offline || self.local_metadata()) {
+            bail!("cannot save metadata without network access");
+        }
+        if self.check_series && self.local_metadata() {
+            bail!("cannot check series without network access");
+        }
+        if self.tape.is_some() && (self.offline || self.local_metadata()) {
+            bail!("cannot record or replay without network access");
+        }
+        if self.watch
+            && (self.recursive_summary
+                || self.check_series
+                || self.save_metadata.is_some()
+                || self.summary_json.is_some())
+        {
+            bail!("--watch cannot be used with --recursive-summary, --check-series, --save-metadata or --summary-json");
+        }
+        if self.resume && self.cache.is_none() {
+            bail!("--resume requires --cache, next to which the journal is kept");
+        }
+        if !(0.0..=100.0).contains(&self.author_threshold) {
+            bail!("author threshold must be between 0 and 100");
+        }
+        if self.dump_matches && self.format == Format::Csv {
+            bail!("--dump-matches cannot be used with CSV output");
+        }
+        if self.format == Format::Ndjson && self.recursive_summary {
+            bail!("--recursive-summary cannot be used with NDJSON output");
+        }
+        if self.sample == Some(0) {
+            bail!("sample size must be positive");
+        }
+        if self.height_ladder.contains(&0) {
+            bail!("heights must be positive");
+        }
+        if self
+            .page_width
+            .is_some_and(|width| !(width.is_finite() && width > 0.0))
+        {
+            bail!("page width must be a positive number");
+        }
+
+        Ok(())
+    }
+}
+
+/// Return the options set from the environment, as command-line arguments.
+///
+/// `--page-width` is set by `CBZLINT_PAGE_WIDTH`, `--strict` by
+/// `CBZLINT_STRICT=1`, etc.
+fn env_args() -> Result<Vec<String>> {
+    let mut args = Vec::new();
+
+    for &flag in ENV_SWITCHES {
+        let var = env_var_name(flag);
+        let Some(value) = env::var_os(&var) else {
+            continue;
+        };
+        match value.to_str() {
+            Some("1" | "true" | "yes") => args.push(flag.to_owned()),
+            Some("" | "0" | "false" | "no") => (),
+            _ => bail!("invalid value for `{var}`, expected a boolean"),
+        }
+    }
+    for &flag in ENV_VALUES {
+        if let Some(value) = env_value(flag)? {
+            args.extend([flag.to_owned(), value]);
+        }
+    }
+
+    Ok(args)
+}
+
+/// Return the value of the option `flag` set from the environment, if any.
+fn env_value(flag: &str) -> Result<Option<String>> {
+    let var = env_var_name(flag);
+
+    match env::var(&var) {
+        Ok(value) => Ok(Some(value)),
+        Err(env::VarError::NotPresent) => Ok(None),
+        Err(err) => Err(err).with_context(|| format!("invalid value for `{var}`")),
+    }
+}
+
+/// Return the name of the environment variable setting `flag`.
+fn env_var_name(flag: &str) -> String {
+    format!(
+        "{ENV_PREFIX}{}",
+        flag.trim_start_matches('-')
+            .replace('-', "_")
+            .to_uppercase()
+    )
+}
+
+/// Parse the value of the option `flag`.
+fn value<T>(args: &mut impl Iterator<Item = String>, flag: &str) -> Result<T>
+where
+    T: FromStr,
+    T::Err: Into<Error>,
+{
+    let value = args
+        .next()
+        .with_context(|| format!("missing value for `{flag}`"))?;
+
+    value
+        .parse()
+        .map_err(Into::into)
+        .with_context(|| format!("invalid value `{value}` for `{flag}`"))
+}
+
+/// Parse a comma-separated list of languages.
+fn languages(list: &str) -> Result<Vec<Language>> {
+    list.split(',')
+        .map(|language| {
+            language
+                .trim()
+                .parse()
+                .with_context(|| format!("invalid language `{language}`"))
+        })
+        .collect()
+}
+
+/// Parse a comma-separated list of heights, in pixels.
+fn heights(list: &str) -> Result<Vec<usize>> {
+    list.split(',')
+        .map(|height| {
+            height
+                .trim()
+                .parse()
+                .with_context(|| format!("invalid height `{height}`"))
+        })
+        .collect()
+}
@@ -0,0 +1,164 @@
+//! Validation of the `ComicInfo.xml` metadata, against the `ComicInfo` schema.
+
+use std::path::Path;
+
+/// Name of the metadata file.
+const COMICINFO_FILE: &str = "ComicInfo.xml";
+
+/// Elements of the `ComicInfo` schema (up to v2.1).
+const ELEMENTS: &[&str] = &[
+    "Title",
+    "Series",
+    "Number",
+    "Count",
+    "Volume",
+    "AlternateSeries",
+    "AlternateNumber",
+    "AlternateCount",
+    "Summary",
+    "Notes",
+    "Year",
+    "Month",
+    "Day",
+    "Writer",
+    "Penciller",
+    "Inker",
+    "Colorist",
+    "Letterer",
+    "CoverArtist",
+    "Editor",
+    "Translator",
+    "Publisher",
+    "Imprint",
+    "Genre",
+    "Tags",
+    "Web",
+    "PageCount",
+    "LanguageISO",
+    "Format",
+    "BlackAndWhite",
+    "Manga",
+    "Characters",
+    "Teams",
+    "Locations",
+    "ScanInformation",
+    "StoryArc",
+    "StoryArcNumber",
+    "SeriesGroup",
+    "AgeRating",
+    "Pages",
+    "CommunityRating",
+    "MainCharacterOrTeam",
+    "Review",
+    "GTIN",
+];
+
+/// Elements holding an integer.
+const INTEGER_ELEMENTS: &[&str] = &[
+    "Count",
+    "Volume",
+    "AlternateCount",
+    "Year",
+    "Month",
+    "Day",
+    "PageCount",
+];
+
+/// Accepted values of the enumerated elements.
+const ENUM_ELEMENTS: &[(&str, &[&str])] = &[
+    ("BlackAndWhite", &["Unknown", "No", "Yes"]),
+    ("Manga", &["Unknown", "No", "Yes", "YesAndRightToLeft"]),
+];
+
+/// Check if the archive entry `name` is the metadata file.
+pub(crate) fn is_comicinfo(name: &str) -> bool {
+    Path::new(name)
+        .file_name()
+        .is_some_and(|file_name| file_name.eq_ignore_ascii_case(COMICINFO_FILE))
+}
+
+/// Validate the metadata file content, and return the first problem found, if
+/// any.
+pub(crate) fn validate(content: &[u8]) -> Option<String> {
+    let text = match std::str::from_utf8(content) {
+        Ok(text) => text,
+        Err(err) => return Some(format!("not UTF-8: {err}")),
+    };
+    let doc = match roxmltree::Document::parse(text) {
+        Ok(doc) => doc,
+        Err(err) => return Some(format!("malformed XML: {err}")),
+    };
+    let root = doc.root_element();
+    if root.tag_name().name() != "ComicInfo" {
+        return Some(format!(
+            "root element is <{}>, expected <ComicInfo>",
+            root.tag_name().name()
+        ));
+    }
+
+    root.children()
+        .filter(roxmltree::Node::is_element)
+        .find_map(validate_element)
+}
+
+/// Validate an element of `<ComicInfo>`.
+fn validate_element(node: roxmltree::Node<'_, '_>) -> Option<String> {
+    let name = node.tag_name().name();
+    let value = node.text().unwrap_or_default().trim();
+
+    if !ELEMENTS.contains(&name) {
+        return Some(format!("unknown element <{name}>"));
+    }
+    if INTEGER_ELEMENTS.contains(&name) && value.parse::<i64>().is_err() {
+        return Some(format!("<{name}> must be an integer, not `{value}`"));
+    }
+    let in_range = match name {
+        "Month" => value
+            .parse()
+            .map_or(true, |month: u8| (1..=12).contains(&month)),
+        "Day" => value
+            .parse()
+            .map_or(true, |day: u8| (1..=31).contains(&day)),
+        "CommunityRating" => value
+            .parse()
+            .is_ok_and(|rating: f64| (0.0..=5.0).contains(&rating)),
+        _ => true,
+    };
+    if !in_range {
+        return Some(format!("<{name}> is out of range (`{value}`)"));
+    }
+    if let Some((_, values)) = ENUM_ELEMENTS.iter().find(|(element, _)| *element == name) {
+        if !values.contains(&value) {
+            return Some(format!(
+                "<{name}> must be one of {}, not `{value}`",
+                values.join(", ")
+            ));
+        }
+    }
+    if name == "Pages" {
+        return validate_pages(node);
+    }
+
+    None
+}
+
+/// Validate the `<Pages>` element: a list of `<Page>`, each with an `Image`
+/// index.
+fn validate_pages(pages: roxmltree::Node<'_, '_>) -> Option<String> {
+    pages
+        .children()
+        .filter(roxmltree::Node::is_element)
+        .find_map(|page| {
+            if page.tag_name().name() != "Page" {
+                return Some(format!(
+                    "unknown element <{}> in <Pages>",
+                    page.tag_name().name()
+                ));
+            }
+            match page.attribute("Image") {
+                Some(image) if image.parse::<u32>().is_ok() => None,
+                Some(image) => Some(format!("<Page> Image must be an integer, not `{image}`")),
+                None => Some("<Page> without Image attribute".to_owned()),
+            }
+        })
+}
@@ -0,0 +1,215 @@
+//! Comparison of two copies of a library.
+
+use crate::{
+    archive, cbz,
+    cli::{Format, Options},
+    outcome::Outcome,
+    picture, termio,
+};
+use anyhow::{bail, Context, Result};
+use serde_json::json;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    ffi::OsStr,
+    fs,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+/// What is compared between two copies of a book.
+#[derive(Debug)]
+struct Summary {
+    /// Number of images.
+    pages: usize,
+    /// Widths of the images.
+    widths: BTreeSet<usize>,
+    /// Modification dates of the entries (`YYYY-MM-DD`).
+    dates: BTreeSet<String>,
+}
+
+impl Summary {
+    /// Summarize the archive at `path`.
+    fn new(path: &Path) -> Result<Self> {
+        let file = fs::File::open(path).context("open error")?;
+        let mut book = archive::open(path, Box::new(file))?;
+        let mut summary = Self {
+            pages: 0,
+            widths: BTreeSet::new(),
+            dates: BTreeSet::new(),
+        };
+
+        for i in 0..book.len() {
+            let mut entry = book.entry(i)?;
+            // Only the pages are compared, like they are checked.
+            if !entry.is_file || cbz::is_junk(&entry.name) || !cbz::is_image(&entry.name) {
+                continue;
+            }
+
+            if let Some(date) = entry.modified {
+                summary.dates.insert(format!(
+                    "{:04}-{:02}-{:02}",
+                    date.year(),
+                    date.month(),
+                    date.day()
+                ));
+            }
+
+            let mut bytes = Vec::new();
+            entry
+                .reader
+                .read_to_end(&mut bytes)
+                .with_context(|| format!("failed to read image {}", entry.name))?;
+            let picture = picture::inspect(&bytes)
+                .with_context(|| format!("cannot inspect image {}", entry.name))?;
+            summary.pages += 1;
+            summary.widths.insert(picture.width);
+        }
+
+        Ok(summary)
+    }
+
+    /// List the differences with `other`.
+    fn diff(&self, other: &Self) -> Vec<String> {
+        let mut differences = Vec::new();
+
+        if self.pages != other.pages {
+            differences.push(format!("page count: {} vs {}", self.pages, other.pages));
+        }
+        if self.widths != other.widths {
+            differences.push(format!(
+                "widths: {} vs {}",
+                join(&self.widths),
+                join(&other.widths)
+            ));
+        }
+        if self.dates != other.dates {
+            differences.push(format!(
+                "dates: {} vs {}",
+                join(&self.dates),
+                join(&other.dates)
+            ));
+        }
+
+        differences
+    }
+}
+
+/// Compare the books of the directories `a` and `b`, matched by file name,
+/// and report their differences.
+///
+/// Only the archives are compared, without any network access.
+///
+/// The outcome is invalid if books differ (or are missing from a directory),
+/// and unchecked if some couldn't be read.
+pub(crate) fn compare(a: &Path, b: &Path, opts: &Options) -> Result<Outcome> {
+    if opts.format == Format::Csv {
+        bail!("CSV output is not supported by --compare");
+    }
+    if opts.format == Format::Ndjson {
+        bail!("NDJSON output is not supported by --compare");
+    }
+    if opts.format == Format::Table {
+        bail!("table output is not supported by --compare");
+    }
+
+    let books_a = list_books(a)?;
+    let books_b = list_books(b)?;
+    let names = books_a
+        .keys()
+        .chain(books_b.keys())
+        .collect::<BTreeSet<_>>();
+
+    let mut reports = Vec::new();
+    let mut outcome = Outcome::Ok;
+    for name in names {
+        let differences = match (books_a.get(name), books_b.get(name)) {
+            (Some(path_a), Some(path_b)) => compare_books(path_a, path_b),
+            (Some(_), None) => Ok(vec![format!("missing from {}", b.display())]),
+            (None, Some(_)) => Ok(vec![format!("missing from {}", a.display())]),
+            (None, None) => unreachable!("name comes from one of the directories"),
+        };
+        outcome.record(match &differences {
+            Ok(differences) if differences.is_empty() => Outcome::Ok,
+            Ok(_) => Outcome::Invalid,
+            Err(_) => Outcome::Unchecked,
+        });
+
+        if opts.format == Format::Json {
+            reports.push(match differences {
+                Ok(differences) => json!({
+                    "file": name,
+                    "ok": differences.is_empty(),
+                    "differences": differences,
+                }),
+                Err(err) => json!({
+                    "file": name,
+                    "ok": false,
+                    "failure": format!("{err:#}"),
+                }),
+            });
+        } else {
+            print_comparison(name, &differences);
+        }
+    }
+
+    if opts.format == Format::Json {
+        let json = serde_json::to_string_pretty(&json!({ "compare": reports }))
+            .context("failed to serialize results")?;
+        println!("{json}");
+    }
+
+    Ok(outcome)
+}
+
+/// Compare two copies of a book.
+fn compare_books(a: &Path, b: &Path) -> Result<Vec<String>> {
+    let summary_a = Summary::new(a).with_context(|| format!("cannot read {}", a.display()))?;
+    let summary_b = Summary::new(b).with_context(|| format!("cannot read {}", b.display()))?;
+
+    Ok(summary_a.diff(&summary_b))
+}
+
+/// List the CBZ files in `dir`, by file name.
+fn list_books(dir: &Path) -> Result<BTreeMap<String, PathBuf>> {
+    let entries =
+        fs::read_dir(dir).with_context(|| format!("failed to read dir {}", dir.display()))?;
+    let mut books = BTreeMap::new();
+
+    for entry in entries {
+        let path = entry
+            .with_context(|| format!("cannot access entry under {}", dir.display()))?
+            .path();
+        if !archive::is_book(&path) {
+            continue;
+        }
+        if let Some(name) = path.file_name().and_then(OsStr::to_str) {
+            books.insert(name.to_owned(), path.clone());
+        }
+    }
+
+    Ok(books)
+}
+
+/// Print the differences between two copies of a book.
+fn print_comparison(name: &str, differences: &Result<Vec<String>>) {
+    match differences {
+        Ok(differences) if differences.is_empty() => termio::print_ok(name),
+        Ok(differences) => {
+            termio::print_err(name);
+            for difference in differences {
+                println!("==> {difference}");
+            }
+        }
+        Err(err) => termio::print_err(&format!("failed to compare {name}: {err:?}")),
+    }
+    println!();
+}
+
+/// Join the values with commas.
+fn join<T: ToString>(values: &BTreeSet<T>) -> String {
+    values
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
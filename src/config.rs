@@ -0,0 +1,255 @@
+//! Configuration file.
+
+use crate::error::CHECKS;
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::Path,
+};
+
+/// Name of the default configuration file, in the current directory.
+pub(crate) const CONFIG_FILE: &str = "cbzlint.toml";
+
+/// Content of the configuration file written by `--init`.
+const TEMPLATE: &str = r#"# cbzlint configuration file.
+#
+# Every key is optional, the values below are the defaults.
+
+# Authors aliases, when bedetheque spells an author differently from your
+# naming convention (e.g. romanization).
+[aliases]
+# "Eiichiro Oda" = "Oda Eiichiro"
+
+# Images checks.
+[images]
+# Minimum size of an image, in bytes: smaller ones are most likely corrupt.
+min_size = 1024
+# Minimum size of an archive, in bytes per pixel of width and per page: smaller
+# ones are most likely over-compressed or missing data.
+size_factor = 20
+# Largest size of a page, as a multiple of the median page size: larger ones
+# are most likely uncompressed or exported at a higher resolution.
+outlier_factor = 10
+# Smallest margin of the dual pages' width, in pixels: it's otherwise 10% of
+# the page width, too tight for narrow pages.
+min_dual_page_margin = 0
+
+# EXIF metadata.
+[exif]
+# Tags tolerated in the images, any other one is reported.
+allow = ["Orientation"]
+# Report the images embedding a thumbnail, even if its tags are tolerated.
+check_thumbnail = false
+
+# Archives content.
+[archive]
+# Files tolerated alongside the images (glob patterns, e.g. "**/credits.txt"),
+# any other one is reported.
+allow = ["ComicInfo.xml"]
+
+# Metadata extraction from bedetheque.
+[metadata]
+# Labels of the fields listing the writers and the pencillers on album pages
+# (e.g. ["Script"] and ["Art"] for English pages).
+writers = ["Scénario"]
+pencillers = ["Dessin"]
+
+# Severity of the checks ("error", "warning" or "ignore"), by name (e.g.
+# "width", "date", "exif"... see the README for the full list). Minor issues
+# (`extension_case`, `archive_size`) are warnings, every other check is an
+# error.
+[severity]
+# exif = "warning"
+"#;
+
+/// Configuration file content.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct Config {
+    /// Authors aliases (name => alias).
+    pub(crate) aliases: HashMap<String, String>,
+    /// Images checks settings.
+    pub(crate) images: ImagesConfig,
+    /// EXIF metadata settings.
+    pub(crate) exif: ExifConfig,
+    /// Archives content settings.
+    pub(crate) archive: ArchiveConfig,
+    /// Metadata extraction settings.
+    pub(crate) metadata: MetadataConfig,
+    /// Severity of the checks, by name, when not the default one.
+    pub(crate) severity: HashMap<String, Severity>,
+}
+
+/// Severity of a check.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Severity {
+    /// Fail the book.
+    Error,
+    /// Only warn about it.
+    Warning,
+    /// Don't report it at all.
+    Ignore,
+}
+
+/// Images checks settings.
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct ImagesConfig {
+    /// Minimum size of an image, in bytes.
+    pub(crate) min_size: u64,
+    /// Minimum size of an archive, in bytes per pixel of width and per page.
+    pub(crate) size_factor: u64,
+    /// Largest size of a page, as a multiple of the median page size.
+    pub(crate) outlier_factor: usize,
+    /// Smallest margin of the dual pages' width, in pixels.
+    pub(crate) min_dual_page_margin: usize,
+}
+
+impl Default for ImagesConfig {
+    fn default() -> Self {
+        Self {
+            min_size: 1024,
+            size_factor: 20,
+            outlier_factor: 10,
+            min_dual_page_margin: 0,
+        }
+    }
+}
+
+/// EXIF metadata settings.
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct ExifConfig {
+    /// Tags tolerated in the images.
+    pub(crate) allow: Vec<String>,
+    /// Report the images embedding a thumbnail.
+    pub(crate) check_thumbnail: bool,
+}
+
+impl Default for ExifConfig {
+    fn default() -> Self {
+        Self {
+            // Harmless, and embedded by many scanners.
+            allow: vec!["Orientation".to_owned()],
+            check_thumbnail: false,
+        }
+    }
+}
+
+/// Archives content settings.
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct ArchiveConfig {
+    /// Patterns of the files tolerated alongside the images.
+    pub(crate) allow: Vec<String>,
+}
+
+impl Default for ArchiveConfig {
+    fn default() -> Self {
+        Self {
+            // Metadata read by most readers.
+            allow: vec!["ComicInfo.xml".to_owned()],
+        }
+    }
+}
+
+/// Metadata extraction settings.
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct MetadataConfig {
+    /// Labels of the fields listing the writers.
+    pub(crate) writers: Vec<String>,
+    /// Labels of the fields listing the pencillers.
+    pub(crate) pencillers: Vec<String>,
+}
+
+impl Default for MetadataConfig {
+    fn default() -> Self {
+        Self {
+            // Labels of the French pages.
+            writers: vec!["Scénario".to_owned()],
+            pencillers: vec!["Dessin".to_owned()],
+        }
+    }
+}
+
+impl Config {
+    /// Load the configuration from `path`.
+    ///
+    /// Without explicit path, the default configuration file is used if it
+    /// exists.
+    pub(crate) fn load(path: Option<&Path>) -> Result<Self> {
+        let (path, required) = match path {
+            Some(path) => (path, true),
+            None => (Path::new(CONFIG_FILE), false),
+        };
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(err) if !required && err.kind() == io::ErrorKind::NotFound => {
+                return Ok(Self::default())
+            }
+            Err(err) => {
+                return Err(err).with_context(|| format!("failed to read {}", path.display()))
+            }
+        };
+
+        let config: Self = toml::from_str(&content)
+            .with_context(|| format!("invalid config {}", path.display()))?;
+        if let Some(check) = config
+            .severity
+            .keys()
+            .find(|check| !CHECKS.contains(&check.as_str()))
+        {
+            bail!(
+                "invalid config {}: unknown check `{check}` in [severity]",
+                path.display()
+            );
+        }
+        if config.images.outlier_factor == 0 {
+            bail!(
+                "invalid config {}: outlier_factor must be at least 1 in [images]",
+                path.display()
+            );
+        }
+        if config.metadata.writers.is_empty() && config.metadata.pencillers.is_empty() {
+            bail!(
+                "invalid config {}: no authors label in [metadata]",
+                path.display()
+            );
+        }
+
+        Ok(config)
+    }
+}
+
+/// Write a commented configuration file, with every supported key, in the
+/// current directory.
+///
+/// An existing file is only overwritten if `force` is set.
+pub(crate) fn init(force: bool) -> Result<()> {
+    let mut options = OpenOptions::new();
+    options.write(true);
+    if force {
+        options.create(true).truncate(true);
+    } else {
+        options.create_new(true);
+    }
+
+    let mut file = match options.open(CONFIG_FILE) {
+        Ok(file) => file,
+        Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {
+            bail!("{CONFIG_FILE} already exists, use --force to overwrite it")
+        }
+        Err(err) => return Err(err).with_context(|| format!("failed to create {CONFIG_FILE}")),
+    };
+
+    file.write_all(TEMPLATE.as_bytes())
+        .with_context(|| format!("failed to write {CONFIG_FILE}"))?;
+    println!("{CONFIG_FILE} written");
+
+    Ok(())
+}
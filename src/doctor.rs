@@ -0,0 +1,103 @@
+//! Diagnostics of the environment, for `--doctor`.
+
+use crate::{bedetheque, cli::Options, config::Config, termio};
+use anyhow::{Context, Result};
+use std::{
+    fs::{self, OpenOptions},
+    path::Path,
+    process::ExitCode,
+};
+
+/// Name of the file written to check that a directory is writable.
+const PROBE_FILE: &str = ".cbzlint-doctor";
+
+/// Check the environment (configuration, network access, writable paths) and
+/// report each check, with a hint on failure.
+///
+/// Returns the exit code: 0 if every check passed, 1 otherwise.
+pub(crate) fn doctor(opts: &Options) -> ExitCode {
+    let mut ok = true;
+
+    ok &= report(
+        "configuration",
+        Config::load(opts.config_path.as_deref()).map(drop),
+        "Fix the configuration file, or write a new one with --init --force.",
+    );
+
+    let client = bedetheque::Client::new(
+        None,
+        opts.languages.clone(),
+        opts.search_field,
+        opts.max_requests,
+        opts.reuse_cache,
+        opts.author_labels.clone(),
+    );
+    match client.get_csrf_token() {
+        Err(err) if bedetheque::is_network_error(&err) => {
+            ok &= report(
+                "bedetheque reachable",
+                Err(err),
+                "Check your network access. HTTP 403 or 429 means that bedetheque is \
+                 blocking you: wait a bit, and send fewer requests (see --max-requests).",
+            );
+        }
+        token => {
+            termio::print_ok("bedetheque reachable");
+            ok &= report(
+                "bedetheque search form",
+                token.map(drop),
+                "bedetheque layout probably changed, update cbzlint.",
+            );
+        }
+    }
+
+    if let Some(path) = &opts.cache {
+        ok &= report(
+            "cache writable",
+            check_writable(path.parent().unwrap_or_else(|| Path::new(""))),
+            "Use a cache file in a writable directory (see --cache).",
+        );
+    }
+
+    if ok {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+/// Print the result of a check, with a hint on failure.
+///
+/// Returns whether the check passed.
+fn report(name: &str, result: Result<()>, hint: &str) -> bool {
+    match result {
+        Ok(()) => {
+            termio::print_ok(name);
+            true
+        }
+        Err(err) => {
+            termio::print_err(&format!("{name}: {err:#}"));
+            println!("    {hint}");
+            false
+        }
+    }
+}
+
+/// Check that files can be created in `dir`.
+fn check_writable(dir: &Path) -> Result<()> {
+    // An empty parent means the current directory.
+    let dir = if dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        dir
+    };
+    let probe = dir.join(PROBE_FILE);
+
+    OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&probe)
+        .with_context(|| format!("cannot write in {}", dir.display()))?;
+    fs::remove_file(&probe).with_context(|| format!("cannot remove {}", probe.display()))
+}
@@ -1,18 +1,144 @@
 use std::{collections::BTreeSet, fmt};
 
+/// Names of the checks, to set their severity.
+pub(crate) const CHECKS: &[&str] = &[
+    "authors",
+    "author_order",
+    "year",
+    "first_edition",
+    "volume",
+    "filename",
+    "width",
+    "height",
+    "even_dimensions",
+    "date",
+    "exif",
+    "thumbnail",
+    "orientation",
+    "dpi",
+    "jpeg_encoding",
+    "quality",
+    "cmyk",
+    "subsampling",
+    "formats",
+    "bit_depth",
+    "image_size",
+    "trailing_garbage",
+    "animated",
+    "unsupported_format",
+    "cover",
+    "cover_spread",
+    "extension_case",
+    "extra_files",
+    "comicinfo",
+    "corrupt_archive",
+    "archive_size",
+    "size_outlier",
+    "min_pages",
+    "max_pages",
+];
+
 pub(crate) enum Error {
-    Authors(String),
+    Authors(String, f64),
     Year(BTreeSet<u16>),
     Width,
     Date,
     Exif,
+    Dpi(String, f64),
+    MissingVolume(u8),
+    ProgressiveJpeg(String),
+    BaselineJpeg(String),
+    LowQuality(String, f64),
+    MixedFormats(Vec<String>),
+    TinyImage(String, usize),
+    CoverOrientation(String),
+    MixedBitDepth(Vec<String>),
+    InconsistentExtensionCase(Vec<String>),
+    CorruptArchive(String),
+    VolumeMismatch(u8),
+    FilenameMismatch(String),
+    FirstEditionYear(u16),
+    Orientation(String),
+    ExtraFiles(Vec<String>),
+    TooManyPages(usize),
+    SuspiciouslySmall(u64, u64),
+    OddDimension(String, usize, usize),
+    MixedSubsampling(Vec<String>),
+    TooFewPages(usize),
+    CmykImage(String),
+    AuthorOrder(String),
+    Height(String, usize),
+    EmbeddedThumbnail(String),
+    SizeOutlier(String, usize, usize),
+    CoverIsSpread(String),
+    ComicInfoInvalid(String),
+    TrailingGarbage(String, usize),
+    AnimatedImage(String),
+    UnsupportedFormat(String),
+}
+
+impl Error {
+    /// Return the name of the check that reported the error.
+    pub(crate) fn check(&self) -> &'static str {
+        match self {
+            Self::Authors(_, _) => "authors",
+            Self::AuthorOrder(_) => "author_order",
+            Self::Year(_) => "year",
+            Self::FirstEditionYear(_) => "first_edition",
+            Self::VolumeMismatch(_) => "volume",
+            Self::FilenameMismatch(_) => "filename",
+            // Reported per series, not per book: no severity applies.
+            Self::MissingVolume(_) => "series",
+            Self::Width => "width",
+            Self::Height(_, _) => "height",
+            Self::OddDimension(_, _, _) => "even_dimensions",
+            Self::Date => "date",
+            Self::Exif => "exif",
+            Self::EmbeddedThumbnail(_) => "thumbnail",
+            Self::Orientation(_) => "orientation",
+            Self::Dpi(_, _) => "dpi",
+            Self::ProgressiveJpeg(_) | Self::BaselineJpeg(_) => "jpeg_encoding",
+            Self::LowQuality(_, _) => "quality",
+            Self::CmykImage(_) => "cmyk",
+            Self::MixedSubsampling(_) => "subsampling",
+            Self::MixedFormats(_) => "formats",
+            Self::MixedBitDepth(_) => "bit_depth",
+            Self::TinyImage(_, _) => "image_size",
+            Self::TrailingGarbage(_, _) => "trailing_garbage",
+            Self::AnimatedImage(_) => "animated",
+            Self::UnsupportedFormat(_) => "unsupported_format",
+            Self::CoverOrientation(_) => "cover",
+            Self::CoverIsSpread(_) => "cover_spread",
+            Self::InconsistentExtensionCase(_) => "extension_case",
+            Self::ExtraFiles(_) => "extra_files",
+            Self::ComicInfoInvalid(_) => "comicinfo",
+            Self::CorruptArchive(_) => "corrupt_archive",
+            Self::SuspiciouslySmall(_, _) => "archive_size",
+            Self::SizeOutlier(_, _, _) => "size_outlier",
+            Self::TooFewPages(_) => "min_pages",
+            Self::TooManyPages(_) => "max_pages",
+        }
+    }
+
+    /// Check if the error is a minor issue, only reported as a warning by
+    /// default.
+    pub(crate) fn is_minor(&self) -> bool {
+        matches!(
+            self,
+            Self::InconsistentExtensionCase(_) | Self::SuspiciouslySmall(_, _)
+        )
+    }
 }
 
 impl fmt::Display for Error {
+    #[allow(clippy::too_many_lines)] // One arm per error, nothing to factorize.
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Self::Authors(authors) => {
-                write!(f, "invalid authors, expected ({authors})")
+            Self::Authors(authors, similarity) => {
+                write!(
+                    f,
+                    "invalid authors, expected ({authors}) ({similarity:.0}% similar)"
+                )
             }
             Self::Year(y) => {
                 let y = y.iter().map(ToString::to_string).collect::<Vec<_>>();
@@ -31,6 +157,118 @@ impl fmt::Display for Error {
             Self::Exif => {
                 write!(f, "some images have EXIF metadata")
             }
+            Self::Dpi(entry, dpi) => {
+                write!(f, "image {entry} has an unexpected DPI ({dpi:.0})")
+            }
+            Self::MissingVolume(volume) => {
+                write!(f, "missing volume {volume}")
+            }
+            Self::ProgressiveJpeg(entry) => {
+                write!(f, "image {entry} is a progressive JPEG")
+            }
+            Self::BaselineJpeg(entry) => {
+                write!(f, "image {entry} is a baseline JPEG")
+            }
+            Self::LowQuality(entry, quality) => {
+                write!(f, "image {entry} has a low JPEG quality (~{quality:.0})")
+            }
+            Self::TinyImage(entry, size) => {
+                write!(f, "image {entry} is suspiciously small ({size} bytes)")
+            }
+            Self::CoverOrientation(entry) => {
+                write!(f, "cover {entry} is not in portrait orientation")
+            }
+            Self::CoverIsSpread(entry) => {
+                write!(f, "cover {entry} is a double-page spread")
+            }
+            Self::TrailingGarbage(entry, extra) => {
+                write!(f, "image {entry} has {extra} bytes after the image data")
+            }
+            Self::AnimatedImage(entry) => write!(f, "image {entry} is animated"),
+            Self::UnsupportedFormat(entry) => {
+                write!(f, "image {entry} uses an unsupported format")
+            }
+            Self::ComicInfoInvalid(detail) => {
+                write!(f, "invalid ComicInfo.xml ({detail})")
+            }
+            Self::MixedBitDepth(entries) => {
+                write!(f, "mixed bit depths, unexpected for {}", entries.join(", "))
+            }
+            Self::InconsistentExtensionCase(entries) => {
+                write!(
+                    f,
+                    "inconsistent extension case, unexpected for {}",
+                    entries.join(", ")
+                )
+            }
+            Self::CorruptArchive(detail) => {
+                write!(f, "corrupt archive ({detail})")
+            }
+            Self::VolumeMismatch(volume) => {
+                write!(f, "volume mismatch, bedetheque page is for volume {volume}")
+            }
+            Self::FilenameMismatch(expected) => {
+                write!(f, "file name mismatch, expected {expected}")
+            }
+            Self::FirstEditionYear(year) => {
+                write!(f, "invalid year, expected {year} (first edition)")
+            }
+            Self::Orientation(entry) => {
+                write!(
+                    f,
+                    "image {entry} asks to be rotated or flipped (EXIF orientation)"
+                )
+            }
+            Self::ExtraFiles(entries) => {
+                write!(f, "unexpected files {}", entries.join(", "))
+            }
+            Self::TooManyPages(count) => {
+                write!(f, "too many entries ({count}), archive not checked")
+            }
+            Self::SuspiciouslySmall(size, expected) => {
+                write!(
+                    f,
+                    "archive is suspiciously small ({size} bytes, expected at least {expected})"
+                )
+            }
+            Self::OddDimension(entry, width, height) => {
+                write!(f, "image {entry} has odd dimensions ({width}x{height})")
+            }
+            Self::MixedSubsampling(entries) => {
+                write!(
+                    f,
+                    "mixed chroma subsampling, unexpected for {}",
+                    entries.join(", ")
+                )
+            }
+            Self::TooFewPages(count) => {
+                write!(f, "too few pages ({count}), book is most likely truncated")
+            }
+            Self::CmykImage(entry) => {
+                write!(f, "image {entry} uses the CMYK color space")
+            }
+            Self::AuthorOrder(authors) => {
+                write!(f, "authors in the wrong order, expected ({authors})")
+            }
+            Self::Height(entry, height) => {
+                write!(f, "image {entry} has an unexpected height ({height}px)")
+            }
+            Self::EmbeddedThumbnail(entry) => {
+                write!(f, "image {entry} embeds a thumbnail")
+            }
+            Self::SizeOutlier(entry, size, median) => {
+                write!(
+                    f,
+                    "image {entry} is much larger than the others ({size} bytes, median {median})"
+                )
+            }
+            Self::MixedFormats(entries) => {
+                write!(
+                    f,
+                    "mixed image formats, unexpected for {}",
+                    entries.join(", ")
+                )
+            }
         }
     }
 }
@@ -0,0 +1,117 @@
+//! Parsing of the books' file names, offline.
+
+use anyhow::{bail, Context, Result};
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+/// Regex to extract info from the name of a series' book.
+static SERIES_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+            r#"^(?P<title>.+)(?: T(?P<volume>[0-9]+)) \((?P<authors>.+)\) \((?P<year>[0-9]{4})\) \[\w+-(?P<width>[0-9]+(?:\.[0-9]+)?k?)(?:px)?\]"#,
+        )
+        .expect("valid series regexp")
+});
+
+/// Regex to extract info from the name of a one-shot.
+static ONESHOT_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"^(?P<title>.+) \((?P<authors>.+)\) \((?P<year>[0-9]{4})\) \[\w+-(?P<width>[0-9]+(?:\.[0-9]+)?k?)(?:px)?\]"#,
+    )
+    .expect("valid one-shot regexp")
+});
+
+/// Information extracted from a book's file name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsedName {
+    /// Title of the series, or of the one-shot.
+    pub title: String,
+    /// Volume number, unless a one-shot.
+    pub volume: Option<u8>,
+    /// Authors, `-`-separated.
+    pub authors: String,
+    /// Publication year.
+    pub year: u16,
+    /// Width of the pages, in pixels.
+    pub width: usize,
+}
+
+/// Parse a book's file name, e.g. `Title T01 (Author) (2000) [Digital-1920]`.
+pub fn parse_filename(name: &str) -> Result<ParsedName> {
+    let captures = if let Some(captures) = SERIES_REGEX.captures(name) {
+        captures
+    } else if let Some(captures) = ONESHOT_REGEX.captures(name) {
+        captures
+    } else {
+        bail!("cannot extract info from filename")
+    };
+
+    Ok(ParsedName {
+        title: capture(&captures, "title")?.to_owned(),
+        volume: captures
+            .name("volume")
+            .map(|m| m.as_str().parse::<u8>())
+            .transpose()
+            .context("invalid volume in filename")?,
+        authors: capture(&captures, "authors")?.to_owned(),
+        year: capture(&captures, "year")?
+            .parse::<u16>()
+            .context("invalid year in filename")?,
+        width: parse_width(capture(&captures, "width")?)?,
+    })
+}
+
+/// Return the file name `name` should have for the given metadata, if `name`
+/// can be parsed.
+///
+/// The tags (e.g. `[Digital-1920]`), and what follows, are kept as is.
+pub(crate) fn canonical_name(
+    name: &str,
+    title: &str,
+    volume: Option<u8>,
+    authors: &str,
+    year: u16,
+) -> Option<String> {
+    let captures = SERIES_REGEX
+        .captures(name)
+        .or_else(|| ONESHOT_REGEX.captures(name))?;
+    let width = captures.name("width")?.start();
+    let tags = name[..width].rfind('[')?;
+    let volume = volume
+        .map(|volume| format!(" T{volume:02}"))
+        .unwrap_or_default();
+
+    Some(format!(
+        "{title}{volume} ({authors}) ({year}) {}",
+        &name[tags..]
+    ))
+}
+
+/// Return the text matched by the capture group `name`.
+fn capture<'t>(captures: &regex::Captures<'t>, name: &str) -> Result<&'t str> {
+    captures
+        .name(name)
+        .map(|m| m.as_str())
+        .with_context(|| format!("no {name} in filename"))
+}
+
+/// Parse the width from the file name, in pixels.
+///
+/// Accepts a `k` shorthand (e.g. `1.6k` for 1600), the `px` suffix is already
+/// stripped by the regex.
+fn parse_width(width: &str) -> Result<usize> {
+    let Some(thousands) = width.strip_suffix('k') else {
+        return width.parse().context("invalid width in filename");
+    };
+
+    let pixels = (thousands
+        .parse::<f64>()
+        .context("invalid width in filename")?
+        * 1000.0)
+        .round();
+    if !(1.0..=1_000_000.0).contains(&pixels) {
+        bail!("invalid width `{width}` in filename");
+    }
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)] // Checked above.
+    Ok(pixels as usize)
+}
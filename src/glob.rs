@@ -0,0 +1,108 @@
+//! Minimal glob patterns matching.
+
+/// A glob pattern.
+///
+/// Supported syntax:
+/// - `?` matches any single character, except `/`.
+/// - `*` matches any sequence of characters, except `/`.
+/// - `**` matches any sequence of characters, `/` included.
+/// - `[abc]`, `[a-z]` and `[!abc]` match a character from (or not from) a set.
+#[derive(Debug, Clone)]
+pub(crate) struct Pattern(Vec<char>);
+
+impl Pattern {
+    /// Initialize a new pattern.
+    pub(crate) fn new(pattern: &str) -> Self {
+        Self(pattern.chars().collect())
+    }
+
+    /// Check if `text` matches the pattern.
+    pub(crate) fn matches(&self, text: &str) -> bool {
+        let text = text.chars().collect::<Vec<_>>();
+
+        matches(&self.0, &text)
+    }
+}
+
+/// Recursively match `text` against `pattern`.
+fn matches(pattern: &[char], text: &[char]) -> bool {
+    match pattern.split_first() {
+        None => text.is_empty(),
+        Some(('*', rest)) => {
+            if let Some(rest) = rest.strip_prefix(&['*']) {
+                // `**/` can also match zero directory.
+                if let Some(rest) = rest.strip_prefix(&['/']) {
+                    return (0..=text.len())
+                        .filter(|&i| i == 0 || text[i - 1] == '/')
+                        .any(|i| matches(rest, &text[i..]));
+                }
+                return (0..=text.len()).any(|i| matches(rest, &text[i..]));
+            }
+            // A single star stops at the path separator.
+            let end = text.iter().position(|&c| c == '/').unwrap_or(text.len());
+            (0..=end).any(|i| matches(rest, &text[i..]))
+        }
+        Some(('?', rest)) => match text.split_first() {
+            Some((&c, text)) => c != '/' && matches(rest, text),
+            None => false,
+        },
+        Some(('[', rest)) => match (text.split_first(), parse_class(rest)) {
+            (Some((&c, text)), Some((class, rest))) => {
+                c != '/' && class.contains(c) && matches(rest, text)
+            }
+            // Unterminated class: treat the bracket literally.
+            (Some((&c, text)), None) => c == '[' && matches(rest, text),
+            (None, _) => false,
+        },
+        Some((&p, rest)) => match text.split_first() {
+            Some((&c, text)) => c == p && matches(rest, text),
+            None => false,
+        },
+    }
+}
+
+/// A set of characters, from a `[...]` expression.
+struct Class {
+    negated: bool,
+    ranges: Vec<(char, char)>,
+}
+
+impl Class {
+    /// Check if `c` belongs to the set.
+    fn contains(&self, c: char) -> bool {
+        self.ranges
+            .iter()
+            .any(|&(start, end)| (start..=end).contains(&c))
+            != self.negated
+    }
+}
+
+/// Parse a characters class, `pattern` starting right after the `[`.
+///
+/// Return the class and the remaining pattern, or `None` if the class is not
+/// terminated.
+fn parse_class(pattern: &[char]) -> Option<(Class, &[char])> {
+    let (negated, mut pattern) = match pattern.split_first() {
+        Some(('!', rest)) => (true, rest),
+        _ => (false, pattern),
+    };
+    let mut ranges = Vec::new();
+
+    // A leading `]` is part of the set.
+    let mut first = true;
+    loop {
+        match pattern {
+            [']', rest @ ..] if !first => return Some((Class { negated, ranges }, rest)),
+            [start, '-', end, rest @ ..] if *end != ']' => {
+                ranges.push((*start, *end));
+                pattern = rest;
+            }
+            [c, rest @ ..] => {
+                ranges.push((*c, *c));
+                pattern = rest;
+            }
+            [] => return None,
+        }
+        first = false;
+    }
+}
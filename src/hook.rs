@@ -0,0 +1,51 @@
+//! Commands run on the books' check results.
+
+use crate::{outcome::Outcome, termio};
+use std::{path::Path, process::Command};
+
+/// Placeholder for the book's path, in the commands.
+const PATH_PLACEHOLDER: &str = "{}";
+
+/// Placeholder for the book's entry in its collection archive, in the
+/// commands.
+const ENTRY_PLACEHOLDER: &str = "{entry}";
+
+/// Run the command `cmd` (with a shell) on the book at `path`, if its check
+/// failed.
+///
+/// For a book inside a collection archive, `path` is the collection's (the
+/// file on disk), and `entry` the book's entry in it; `entry` is empty
+/// otherwise.
+///
+/// Network failures don't count, the book itself may be fine. The command's
+/// failure is reported, but doesn't abort the run.
+pub(crate) fn on_failure(cmd: &str, path: &Path, entry: &str, outcome: Outcome) {
+    if !matches!(outcome, Outcome::Invalid | Outcome::Unchecked) {
+        return;
+    }
+
+    // The path is passed as argument rather than pasted in the command, so
+    // that it doesn't need to be quoted.
+    let script = cmd
+        .replace(ENTRY_PLACEHOLDER, r#""$2""#)
+        .replace(PATH_PLACEHOLDER, r#""$1""#);
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(script)
+        .arg("sh") // `$0`.
+        .arg(path)
+        .arg(entry)
+        .status();
+
+    match status {
+        Ok(status) if status.success() => (),
+        Ok(status) => termio::print_warn(&format!(
+            "--on-failure command for {} exited with {status}",
+            path.display()
+        )),
+        Err(err) => termio::print_warn(&format!(
+            "cannot run --on-failure command for {}: {err}",
+            path.display()
+        )),
+    }
+}
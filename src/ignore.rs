@@ -0,0 +1,79 @@
+//! Support for `.cbzlintignore` files.
+
+use crate::glob::Pattern;
+use anyhow::{Context, Result};
+use std::{fs, io, path::Path};
+
+/// Name of the file listing the books to ignore.
+pub(crate) const IGNORE_FILE: &str = ".cbzlintignore";
+
+/// A rule from an ignore file.
+struct Rule {
+    pattern: Pattern,
+    /// Negated rules (starting with `!`) re-include files.
+    negated: bool,
+}
+
+/// List of books to ignore, gitignore-style.
+#[derive(Default)]
+pub(crate) struct IgnoreList {
+    rules: Vec<Rule>,
+}
+
+impl IgnoreList {
+    /// Load the ignore list from the directory `dir`, if any.
+    pub(crate) fn load(dir: &Path) -> Result<Self> {
+        let path = dir.join(IGNORE_FILE);
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(err) => {
+                return Err(err).with_context(|| format!("failed to read {}", path.display()))
+            }
+        };
+
+        Ok(Self::parse(&content))
+    }
+
+    /// Parse the content of an ignore file.
+    fn parse(content: &str) -> Self {
+        let rules = content
+            .lines()
+            .map(str::trim)
+            // Skip blank lines and comments.
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| {
+                let (negated, line) = match line.strip_prefix('!') {
+                    Some(line) => (true, line),
+                    None => (false, line),
+                };
+                Rule {
+                    // Patterns are always relative to the scanned directory.
+                    pattern: Pattern::new(line.trim_start_matches('/')),
+                    negated,
+                }
+            })
+            .collect();
+
+        Self { rules }
+    }
+
+    /// Check if the file at `path` (relative to the scanned directory) is
+    /// ignored.
+    ///
+    /// Patterns are matched against both the file name and the relative path,
+    /// the last matching rule wins.
+    pub(crate) fn is_ignored(&self, path: &Path) -> bool {
+        let relpath = path.to_string_lossy().replace('\\', "/");
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy())
+            .unwrap_or_default();
+
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| rule.pattern.matches(&name) || rule.pattern.matches(&relpath))
+            .is_some_and(|rule| !rule.negated)
+    }
+}
@@ -0,0 +1,104 @@
+//! Journal of the books that passed, to resume an interrupted run.
+//!
+//! Books are recorded by path and modification time, so that a book modified
+//! since it passed is checked again. An unpacked book (i.e. a directory) is
+//! modified when any of its files is.
+
+use anyhow::{Context, Result};
+use std::{
+    collections::HashSet,
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Journal of the books that passed.
+pub(crate) struct Journal {
+    /// Journal file, each book being appended as soon as it passed.
+    file: File,
+    /// Books that passed in a previous run.
+    passed: HashSet<String>,
+}
+
+impl Journal {
+    /// Open the journal kept next to the `cache` file.
+    ///
+    /// When resuming, the books that passed in the previous run are loaded,
+    /// otherwise the journal starts afresh.
+    pub(crate) fn open(cache: &Path, resume: bool) -> Result<Self> {
+        let path = journal_path(cache);
+        let passed = if resume {
+            match fs::read_to_string(&path) {
+                Ok(content) => content.lines().map(ToOwned::to_owned).collect(),
+                Err(err) if err.kind() == io::ErrorKind::NotFound => HashSet::new(),
+                Err(err) => {
+                    return Err(err)
+                        .with_context(|| format!("failed to read journal {}", path.display()))
+                }
+            }
+        } else {
+            HashSet::new()
+        };
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("failed to open journal {}", path.display()))?;
+        if !resume {
+            file.set_len(0)
+                .with_context(|| format!("failed to reset journal {}", path.display()))?;
+        }
+
+        Ok(Self { file, passed })
+    }
+
+    /// Check if the book at `path` passed in the previous run, and wasn't
+    /// modified since.
+    pub(crate) fn has_passed(&self, path: &Path) -> bool {
+        key(path).is_some_and(|key| self.passed.contains(&key))
+    }
+
+    /// Record that the book at `path` passed.
+    pub(crate) fn record(&mut self, path: &Path) -> Result<()> {
+        // Books that can't be identified (e.g. in a collection) are always
+        // checked again.
+        let Some(key) = key(path) else {
+            return Ok(());
+        };
+
+        writeln!(self.file, "{key}").context("failed to write journal")
+    }
+}
+
+/// Return the path of the journal kept next to the `cache` file.
+fn journal_path(cache: &Path) -> PathBuf {
+    cache.with_extension("journal")
+}
+
+/// Return the key of the book at `path`: its modification time and its path.
+fn key(path: &Path) -> Option<String> {
+    let seconds = modified(path)?.duration_since(UNIX_EPOCH).ok()?.as_secs();
+    let path = fs::canonicalize(path).ok()?;
+
+    Some(format!("{seconds}\t{}", path.to_str()?))
+}
+
+/// Return the modification time of the book at `path`.
+///
+/// Editing a file doesn't change its directory's modification time, so the
+/// latest one of its files is used for an unpacked book.
+fn modified(path: &Path) -> Option<SystemTime> {
+    let metadata = fs::metadata(path).ok()?;
+    let modified = metadata.modified().ok()?;
+    if !metadata.is_dir() {
+        return Some(modified);
+    }
+
+    fs::read_dir(path)
+        .ok()?
+        .map(|entry| entry.and_then(|entry| entry.metadata()?.modified()))
+        .try_fold(modified, |latest, modified| {
+            Some(latest.max(modified.ok()?))
+        })
+}
@@ -0,0 +1,286 @@
+//! Low-level inspection of JPEG images.
+
+use anyhow::{bail, Error};
+use std::str::FromStr;
+
+/// Start Of Image marker.
+const SOI: u8 = 0xD8;
+/// End Of Image marker.
+const EOI: u8 = 0xD9;
+/// Start Of Scan marker.
+const SOS: u8 = 0xDA;
+/// JFIF application segment marker.
+const APP0: u8 = 0xE0;
+/// Define Quantization Table marker.
+const DQT: u8 = 0xDB;
+
+/// Luminance quantization table from the JPEG standard (Annex K), which
+/// corresponds to a quality of 50 with the IJG scaling.
+const STD_LUMINANCE_TABLE: [u16; 64] = [
+    16, 11, 10, 16, 24, 40, 51, 61, 12, 12, 14, 19, 26, 58, 60, 55, 14, 13, 16, 24, 40, 57, 69, 56,
+    14, 17, 22, 29, 51, 87, 80, 62, 18, 22, 37, 56, 68, 109, 103, 77, 24, 35, 55, 64, 81, 104, 113,
+    92, 49, 64, 78, 87, 103, 121, 120, 101, 72, 92, 95, 98, 112, 100, 103, 99,
+];
+
+/// JPEG encoding process.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub(crate) enum Encoding {
+    /// Sequential encoding, the image is decoded top to bottom.
+    Baseline,
+    /// Progressive encoding, the image is decoded in several passes.
+    Progressive,
+}
+
+impl FromStr for Encoding {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "baseline" => Self::Baseline,
+            "progressive" => Self::Progressive,
+            _ => bail!("expected `baseline` or `progressive`"),
+        })
+    }
+}
+
+/// A JPEG marker segment.
+pub(crate) struct Segment<'a> {
+    /// Marker code (the byte following `0xFF`).
+    pub(crate) marker: u8,
+    /// Segment payload, length field excluded.
+    pub(crate) data: &'a [u8],
+}
+
+/// Iterator over the marker segments of a JPEG.
+///
+/// Iteration stops after the first Start Of Scan segment: what follows is
+/// entropy-coded data.
+pub(crate) struct Segments<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Iterator for Segments<'a> {
+    type Item = Segment<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Skip fill bytes.
+        while self.bytes.get(self.pos) == Some(&0xFF) && self.bytes.get(self.pos + 1) == Some(&0xFF)
+        {
+            self.pos += 1;
+        }
+        if self.bytes.get(self.pos) != Some(&0xFF) {
+            return None;
+        }
+        let marker = *self.bytes.get(self.pos + 1)?;
+
+        // Standalone markers, without payload.
+        if marker == 0x01 || (0xD0..=0xD7).contains(&marker) {
+            self.pos += 2;
+            return Some(Segment { marker, data: &[] });
+        }
+        if marker == EOI {
+            self.pos = self.bytes.len();
+            return Some(Segment { marker, data: &[] });
+        }
+
+        let len = usize::from(read_u16(self.bytes, self.pos + 2)?);
+        let data = self.bytes.get(self.pos + 4..self.pos + 2 + len)?;
+        self.pos = if marker == SOS {
+            // Don't try to parse the scan data.
+            self.bytes.len()
+        } else {
+            self.pos + 2 + len
+        };
+
+        Some(Segment { marker, data })
+    }
+}
+
+/// Check if `bytes` looks like a JPEG image.
+pub(crate) fn is_jpeg(bytes: &[u8]) -> bool {
+    bytes.starts_with(&[0xFF, SOI])
+}
+
+/// Iterate over the marker segments of a JPEG image.
+///
+/// Yields nothing if `bytes` is not a JPEG.
+pub(crate) fn segments(bytes: &[u8]) -> Segments<'_> {
+    Segments {
+        bytes,
+        // Skip the SOI marker, or everything if it's not a JPEG.
+        pos: if is_jpeg(bytes) { 2 } else { bytes.len() },
+    }
+}
+
+/// Return the encoding process of a JPEG image, if `bytes` is a JPEG.
+pub(crate) fn encoding(bytes: &[u8]) -> Option<Encoding> {
+    segments(bytes).find_map(|segment| match segment.marker {
+        // Sequential (baseline, extended, lossless) Start Of Frame markers.
+        0xC0 | 0xC1 | 0xC3 | 0xC5 | 0xC7 | 0xC9 | 0xCB | 0xCD | 0xCF => Some(Encoding::Baseline),
+        // Progressive Start Of Frame markers.
+        0xC2 | 0xC6 | 0xCA | 0xCE => Some(Encoding::Progressive),
+        _ => None,
+    })
+}
+
+/// Return the chroma subsampling (e.g. `4:2:0`) of a JPEG image, if `bytes` is
+/// a color JPEG.
+pub(crate) fn subsampling(bytes: &[u8]) -> Option<String> {
+    let frame = frame(bytes)?;
+    let components = usize::from(*frame.get(5)?);
+    if components < 3 {
+        // Grayscale, no chroma.
+        return None;
+    }
+    // Each component has an id, its sampling factors and a table id.
+    let factors = |i: usize| frame.get(6 + 3 * i + 1).map(|f| (f >> 4, f & 0x0F));
+    let (luma_h, luma_v) = factors(0)?;
+    let (chroma_h, chroma_v) = factors(1)?;
+
+    Some(
+        match (luma_h / chroma_h.max(1), luma_v / chroma_v.max(1)) {
+            (1, 1) => "4:4:4",
+            (2, 1) => "4:2:2",
+            (2, 2) => "4:2:0",
+            (1, 2) => "4:4:0",
+            (4, 1) => "4:1:1",
+            (h, v) => return Some(format!("{h}x{v}")),
+        }
+        .to_owned(),
+    )
+}
+
+/// Check if `bytes` is a CMYK JPEG image.
+///
+/// Four components means CMYK, or YCCK (its YCbCr-transformed form, per the
+/// Adobe APP14 segment): both are rendered with wrong colors by many readers.
+pub(crate) fn is_cmyk(bytes: &[u8]) -> bool {
+    frame(bytes).and_then(|frame| frame.get(5)) == Some(&4)
+}
+
+/// Return the data of the Start Of Frame segment of a JPEG image, if any.
+///
+/// The number of components is at offset 5, after the precision (1 byte) and
+/// the dimensions (4 bytes).
+fn frame(bytes: &[u8]) -> Option<&[u8]> {
+    segments(bytes)
+        .find(|segment| {
+            matches!(segment.marker, 0xC0..=0xCF) && ![0xC4, 0xC8, 0xCC].contains(&segment.marker)
+        })
+        .map(|segment| segment.data)
+}
+
+/// Estimate the quality (from 1 to 100, IJG scale) of a JPEG image, if
+/// `bytes` is a JPEG.
+///
+/// The estimation compares the luminance quantization table with the standard
+/// one, so it's only approximate for images produced by non-IJG encoders.
+pub(crate) fn estimate_quality(bytes: &[u8]) -> Option<f64> {
+    let table = segments(bytes)
+        .filter(|segment| segment.marker == DQT)
+        .find_map(|segment| luminance_table_sum(segment.data))?;
+    let reference = STD_LUMINANCE_TABLE
+        .iter()
+        .copied()
+        .map(u32::from)
+        .sum::<u32>();
+    // Scaling factor, in percent, applied to the standard table.
+    let scale = f64::from(table) * 100.0 / f64::from(reference);
+    let quality = if scale <= 100.0 {
+        (200.0 - scale) / 2.0
+    } else {
+        5000.0 / scale
+    };
+
+    Some(quality.clamp(1.0, 100.0))
+}
+
+/// Return the sum of the coefficients of the luminance table (i.e. table 0)
+/// from a DQT segment, if any.
+fn luminance_table_sum(mut data: &[u8]) -> Option<u32> {
+    // A segment can define several tables.
+    while let Some((&info, rest)) = data.split_first() {
+        let precision = info >> 4;
+        let id = info & 0x0F;
+        let size = if precision == 0 { 64 } else { 128 };
+        let table = rest.get(..size)?;
+
+        if id == 0 {
+            return Some(if precision == 0 {
+                table.iter().copied().map(u32::from).sum()
+            } else {
+                table
+                    .chunks_exact(2)
+                    .map(|pair| u32::from(u16::from_be_bytes([pair[0], pair[1]])))
+                    .sum()
+            });
+        }
+        data = &rest[size..];
+    }
+
+    None
+}
+
+/// Return the horizontal density (in dots per inch) declared in the JFIF
+/// header, if any.
+pub(crate) fn jfif_dpi(bytes: &[u8]) -> Option<f64> {
+    let segment = segments(bytes).find(|segment| segment.marker == APP0)?;
+    let header = segment.data.strip_prefix(b"JFIF\0")?;
+    // Skip the version (2 bytes).
+    let unit = *header.get(2)?;
+    let density = f64::from(read_u16(header, 3)?);
+
+    match unit {
+        // Dots per inch.
+        1 => Some(density),
+        // Dots per cm.
+        2 => Some(density * 2.54),
+        // No unit, only an aspect ratio.
+        _ => None,
+    }
+}
+
+/// Return the length of a JPEG image, up to and including its End Of Image
+/// marker, if `bytes` is a JPEG and the marker is found.
+pub(crate) fn image_len(bytes: &[u8]) -> Option<usize> {
+    if !is_jpeg(bytes) {
+        return None;
+    }
+    let mut pos = 2;
+
+    loop {
+        // Skip fill bytes.
+        while bytes.get(pos) == Some(&0xFF) && bytes.get(pos + 1) == Some(&0xFF) {
+            pos += 1;
+        }
+        if *bytes.get(pos)? != 0xFF {
+            return None;
+        }
+        let marker = *bytes.get(pos + 1)?;
+
+        if marker == EOI {
+            return Some(pos + 2);
+        }
+        // Standalone markers, without payload.
+        if marker == 0x01 || (0xD0..=0xD7).contains(&marker) {
+            pos += 2;
+            continue;
+        }
+        pos += 2 + usize::from(read_u16(bytes, pos + 2)?);
+        if marker == SOS {
+            // The scan data ends at the next marker: 0xFF that's neither
+            // stuffed (followed by 0x00) nor a restart marker.
+            pos += bytes.get(pos..)?.windows(2).position(|pair| {
+                pair[0] == 0xFF && pair[1] != 0x00 && !(0xD0..=0xD7).contains(&pair[1])
+            })?;
+        }
+    }
+}
+
+/// Read a big-endian `u16` at `pos`.
+fn read_u16(bytes: &[u8], pos: usize) -> Option<u16> {
+    let bytes = bytes.get(pos..pos + 2)?;
+
+    Some(u16::from_be_bytes([bytes[0], bytes[1]]))
+}
@@ -0,0 +1,522 @@
+//! A CBZ checker.
+
+// Lints {{{
+
+#![deny(
+    nonstandard_style,
+    rust_2018_idioms,
+    future_incompatible,
+    rustdoc::all,
+    missing_crate_level_docs,
+    missing_docs,
+    unreachable_pub,
+    unsafe_code,
+    unused,
+    unused_crate_dependencies,
+    unused_import_braces,
+    unused_lifetimes,
+    unused_qualifications,
+    variant_size_differences,
+    warnings,
+    clippy::all,
+    clippy::pedantic,
+    clippy::clone_on_ref_ptr,
+    clippy::exit,
+    clippy::filetype_is_file,
+    clippy::float_cmp_const,
+    clippy::lossy_float_literal,
+    clippy::mem_forget,
+    clippy::panic,
+    clippy::rest_pat_in_fully_bound_structs,
+    clippy::unneeded_field_pattern,
+    clippy::verbose_file_reads,
+    clippy::dbg_macro,
+    clippy::let_underscore_must_use,
+    clippy::todo,
+    clippy::unwrap_used,
+    clippy::use_debug
+)]
+#![allow(
+    // The 90’s called and wanted their charset back :p
+    clippy::non_ascii_literal,
+    // For Kuchiki imports.
+    clippy::wildcard_imports,
+    // It's easily outdated and doesn't bring that much value.
+    clippy::missing_errors_doc,
+    // That's OK for this script.
+    clippy::expect_used,
+    clippy::print_stdout,
+)]
+
+// }}}
+
+use anyhow::{Context, Result};
+pub use filename::{parse_filename, ParsedName};
+use outcome::Outcome;
+use provider::MetadataProvider;
+use serde_json::json;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+pub use stream::{check, BookReport, Checks};
+use url::Url;
+
+// Only used by the benchmarks.
+#[cfg(test)]
+use criterion as _;
+
+mod archive;
+mod authors;
+mod bedetheque;
+mod cache;
+mod catalog;
+mod cbz;
+mod cli;
+mod comicinfo;
+mod compare;
+mod config;
+mod doctor;
+mod error;
+mod filename;
+mod glob;
+mod hook;
+mod ignore;
+mod journal;
+mod jpeg;
+mod metadata;
+mod outcome;
+mod output;
+pub mod picture;
+mod preview;
+mod provider;
+mod remote;
+mod sample;
+mod scan;
+mod series;
+mod snapshot;
+mod stream;
+mod sort;
+mod summary;
+mod tape;
+mod template;
+mod termio;
+mod warning;
+mod watch;
+
+/// Run cbzlint, with the options from the command line.
+///
+/// Returns the exit code: 0 if every book is OK, 1 on content errors, 2 if
+/// some books couldn't be checked and 3 if bedetheque couldn't be reached.
+pub fn run() -> Result<ExitCode> {
+    let opts = cli::Options::parse()?;
+    if let Some(res) = run_command(&opts) {
+        return res;
+    }
+
+    let sources = provider::Sources::load(&opts)?;
+    let client = sources.client();
+    let provider = sources.provider();
+    if opts.watch {
+        return watch::watch(provider, &opts).map(|()| ExitCode::SUCCESS);
+    }
+    // Snapshot to save, if requested.
+    let mut new_snapshot = snapshot::Snapshot::default();
+    // Aggregate statistics, saved if requested.
+    let mut summary = summary::Summary::new();
+
+    // Most severe outcome so far.
+    let mut outcome = Outcome::default();
+
+    // Retrieve the list of CBZ to check.
+    // Books that passed, to resume an interrupted run.
+    let mut journal = opts
+        .cache
+        .as_deref()
+        .map(|cache| journal::Journal::open(cache, opts.resume))
+        .transpose()?;
+    // Books given as URLs, removed once checked.
+    let mut downloads = remote::Downloads::new(
+        client.map_or_else(ureq::Agent::new, |client| client.agent().clone()),
+    );
+    let (books, warnings) = collect_books(
+        provider,
+        &opts,
+        journal.as_ref(),
+        &mut downloads,
+        &mut outcome,
+    )?;
+    if opts.format != cli::Format::Json {
+        output::print_warnings(&warnings);
+    }
+    summary.record_skipped(&warnings);
+
+    // Check each book.
+    let mut reports = Reports {
+        warnings,
+        ..Reports::default()
+    };
+    // Volumes found for each series, to check their completeness.
+    let mut series = BTreeMap::<Url, BTreeSet<u8>>::new();
+    let mut results = Vec::with_capacity(books.len());
+    for book in books {
+        let result = book.check(provider, &opts);
+        let book_outcome = Outcome::of_result(&result);
+        outcome.record(book_outcome);
+        summary.record(&result);
+        if let Some(cmd) = &opts.on_failure {
+            // The collection archive for its books, the file on disk.
+            let (path, entry) = book.container().unwrap_or((book.path(), ""));
+            hook::on_failure(cmd, path, entry, book_outcome);
+        }
+        if let (Some(journal), Outcome::Ok) = (&mut journal, book_outcome) {
+            journal.record(book.path())?;
+        }
+        if opts.format == cli::Format::Ndjson {
+            output::print_ndjson(&output::to_json(&book, &result, opts.timings));
+        }
+
+        if let (Some(_), Some(provider), Some(url)) =
+            (&opts.save_metadata, provider, book.ref_url())
+        {
+            // Already in cache, no additional request.
+            if let Ok(info) = provider.metadata(url) {
+                new_snapshot.record(book.file_name(), url, info);
+            }
+        }
+
+        if let (Ok(report), Some(volume)) = (&result, book.volume()) {
+            if let Some(url) = &report.series {
+                series.entry(url.clone()).or_default().insert(volume);
+            }
+        }
+
+        results.push((book, result));
+    }
+
+    sort::sort_results(&mut results, opts.sort);
+    if opts.dump_matches {
+        reports.add_matches(&results, provider);
+    }
+    reports.add_results(results, &opts);
+
+    if let (true, Some(client)) = (opts.check_series, client) {
+        reports.series =
+            check_all_series(client, &series, &opts, &mut outcome);
+    }
+
+    if let Some(path) = &opts.save_metadata {
+        new_snapshot.save(path)?;
+    }
+    if let Some(path) = &opts.summary_json {
+        summary.save(path, client.map(bedetheque::Client::stats))?;
+    }
+    if let (Some(client), Some(path)) = (client, &opts.cache) {
+        cache::save(path, client.lookups())?;
+    }
+    reports.print(client.map(bedetheque::Client::stats), &opts)?;
+
+    Ok(outcome.exit_code())
+}
+
+/// Run the command replacing the books' check (e.g. `--init`), if any.
+fn run_command(opts: &cli::Options) -> Option<Result<ExitCode>> {
+    if opts.init {
+        return Some(config::init(opts.force).map(|()| ExitCode::SUCCESS));
+    }
+    if opts.doctor {
+        return Some(Ok(doctor::doctor(opts)));
+    }
+    if let Some((a, b)) = &opts.compare {
+        return Some(compare::compare(a, b, opts).map(Outcome::exit_code));
+    }
+    if let Some(text) = &opts.normalize_preview {
+        preview::preview(text, &opts.aliases);
+        return Some(Ok(ExitCode::SUCCESS));
+    }
+
+    None
+}
+
+/// Results accumulated for the machine-readable outputs.
+#[derive(Default)]
+struct Reports {
+    /// JSON report of each book.
+    books: Vec<serde_json::Value>,
+    /// JSON report of each directory, for the summary.
+    directories: serde_json::Map<String, serde_json::Value>,
+    /// CSV (or table) row of each book.
+    rows: Vec<output::CsvRow>,
+    /// Warnings not tied to a book's check.
+    warnings: Vec<warning::Warning>,
+    /// JSON report of each series, when checked.
+    series: Vec<serde_json::Value>,
+    /// Bedetheque page matched by each book, if requested.
+    matches: Vec<output::Match>,
+}
+
+impl Reports {
+    /// Print the accumulated results, and the network usage statistics (if
+    /// any), in the requested format.
+    fn print(
+        self,
+        stats: Option<bedetheque::Stats>,
+        opts: &cli::Options,
+    ) -> Result<()> {
+        match opts.format {
+            cli::Format::Json => {
+                let mut json = if opts.recursive_summary {
+                    json!({ "directories": self.directories })
+                } else {
+                    json!({ "books": self.books })
+                };
+                json["warnings"] =
+                    self.warnings.iter().map(ToString::to_string).collect();
+                json["skipped"] = output::skipped_to_json(&self.warnings);
+                if opts.check_series {
+                    json["series"] = self.series.into();
+                }
+                if opts.dump_matches {
+                    json["matches"] = output::matches_to_json(&self.matches);
+                }
+                if let Some(stats) = stats {
+                    json["network"] = output::stats_to_json(&stats);
+                }
+                let json = serde_json::to_string_pretty(&json)
+                    .context("failed to serialize results")?;
+                println!("{json}");
+            },
+            cli::Format::Csv => output::print_csv(&self.rows)?,
+            cli::Format::Ndjson => {
+                output::print_ndjson(&json!({
+                    "skipped": output::skipped_to_json(&self.warnings),
+                }));
+                if opts.dump_matches {
+                    output::print_ndjson(&json!({
+                        "matches": output::matches_to_json(&self.matches),
+                    }));
+                }
+                if let Some(stats) = stats {
+                    output::print_ndjson(
+                        &json!({ "network": output::stats_to_json(&stats) }),
+                    );
+                }
+            },
+            cli::Format::Human | cli::Format::Table => {
+                if opts.format == cli::Format::Table {
+                    output::print_table(&self.rows);
+                }
+                output::print_skipped(&self.warnings);
+                if opts.dump_matches {
+                    output::print_matches(&self.matches);
+                }
+                if let Some(stats) = stats {
+                    output::print_stats(&stats);
+                }
+            },
+        }
+
+        Ok(())
+    }
+
+    /// Record the bedetheque page matched by each book.
+    ///
+    /// The metadata are already in cache, no additional request is sent.
+    fn add_matches(
+        &mut self,
+        results: &[(cbz::Book, Result<cbz::Report>)],
+        provider: Option<&dyn MetadataProvider>,
+    ) {
+        self.matches.extend(results.iter().map(|(book, _)| {
+            let series = provider
+                .zip(book.ref_url())
+                .and_then(|(provider, url)| provider.metadata(url).ok())
+                .and_then(|info| info.series_title);
+            output::Match {
+                file: book.file_name().to_owned(),
+                url: book.ref_url().cloned(),
+                series,
+            }
+        }));
+    }
+
+    /// Report the results of the books' check, grouped by directory for the
+    /// summary.
+    fn add_results(
+        &mut self,
+        results: Vec<(cbz::Book, Result<cbz::Report>)>,
+        opts: &cli::Options,
+    ) {
+        if !opts.recursive_summary {
+            for (book, result) in &results {
+                self.add_book(book, result, opts);
+            }
+            return;
+        }
+
+        let mut directories = BTreeMap::<PathBuf, Vec<_>>::new();
+        for (book, result) in results {
+            let dir = book.path().parent().unwrap_or_else(|| Path::new(""));
+            directories
+                .entry(dir.to_owned())
+                .or_default()
+                .push((book, result));
+        }
+        for (dir, results) in &directories {
+            self.add_directory(dir, results, opts);
+        }
+    }
+
+    /// Report the result of a book's check, in the requested format.
+    fn add_book(
+        &mut self,
+        book: &cbz::Book,
+        result: &Result<cbz::Report>,
+        opts: &cli::Options,
+    ) {
+        match opts.format {
+            cli::Format::Json => {
+                self.books.push(output::to_json(book, result, opts.timings));
+            },
+            cli::Format::Csv | cli::Format::Table => {
+                self.rows.push(output::to_csv(book, result));
+            },
+            cli::Format::Human => output::print_human(book, result, opts),
+            // Already printed, as soon as the book was checked.
+            cli::Format::Ndjson => (),
+        }
+    }
+
+    /// Report the results of the books of a directory, in the requested
+    /// format.
+    fn add_directory(
+        &mut self,
+        dir: &Path,
+        results: &[(cbz::Book, Result<cbz::Report>)],
+        opts: &cli::Options,
+    ) {
+        match opts.format {
+            cli::Format::Json => {
+                self.directories.insert(
+                    dir.display().to_string(),
+                    output::directory_to_json(results, opts.timings),
+                );
+            },
+            cli::Format::Csv | cli::Format::Table => self.rows.extend(
+                results
+                    .iter()
+                    .map(|(book, result)| output::to_csv(book, result)),
+            ),
+            cli::Format::Human => output::print_directory(dir, results, opts),
+            cli::Format::Ndjson => {
+                unreachable!("no summary with NDJSON output")
+            },
+        }
+    }
+}
+
+/// Collect the books to check, with the warnings met along the way.
+///
+/// Books given as URLs are fetched into `downloads`.
+fn collect_books(
+    provider: Option<&dyn MetadataProvider>,
+    opts: &cli::Options,
+    journal: Option<&journal::Journal>,
+    downloads: &mut remote::Downloads,
+    outcome: &mut Outcome,
+) -> Result<(Vec<cbz::Book>, Vec<warning::Warning>)> {
+    let mut warnings = Vec::new();
+    let mut books = Vec::new();
+    for path in &opts.paths {
+        let path = match remote::as_url(path)? {
+            Some(url) => match downloads.fetch(&url) {
+                Ok(path) => path,
+                // Like a book that can't be found.
+                Err(err) => {
+                    outcome.record(Outcome::Network);
+                    warnings.push(warning::Warning::not_loaded(path.clone(), &err));
+                    continue;
+                },
+            },
+            None => path.clone(),
+        };
+        books.extend(
+            scan::get_books(provider, &path, opts, journal, outcome, &mut warnings)
+                .context("failed to collect paths")?,
+        );
+    }
+    warnings.extend(duplicate_urls(&books));
+
+    Ok((books, warnings))
+}
+
+/// Return a warning for each pair of books that resolve to the same
+/// bedetheque URL: one of them is mislabeled.
+fn duplicate_urls(books: &[cbz::Book]) -> Vec<warning::Warning> {
+    let mut resolved = HashMap::<&Url, &str>::new();
+    let mut warnings = Vec::new();
+
+    for book in books {
+        if let Some(url) = book.ref_url() {
+            if let Some(other) = resolved.insert(url, book.file_name()) {
+                warnings.push(warning::Warning::DuplicateUrl(
+                    other.to_owned(),
+                    book.file_name().to_owned(),
+                    url.clone(),
+                ));
+            }
+        }
+    }
+
+    warnings
+}
+
+/// Check the completeness of every series, and report the results.
+///
+/// Returns the JSON reports, if JSON output is enabled. The outcome of each
+/// check is recorded in `outcome`.
+fn check_all_series(
+    client: &bedetheque::Client,
+    series: &BTreeMap<Url, BTreeSet<u8>>,
+    opts: &cli::Options,
+    outcome: &mut Outcome,
+) -> Vec<serde_json::Value> {
+    let mut reports = Vec::new();
+    for (url, volumes) in series {
+        let result = check_series(client, url, volumes);
+        outcome.record(match &result {
+            Ok(missing) if missing.is_empty() => Outcome::Ok,
+            Ok(_) => Outcome::Invalid,
+            Err(err) => Outcome::of_error(err),
+        });
+
+        match opts.format {
+            cli::Format::Json => {
+                reports.push(output::series_to_json(url, &result));
+            },
+            cli::Format::Ndjson => output::print_ndjson(
+                &json!({ "series": output::series_to_json(url, &result) }),
+            ),
+            cli::Format::Csv | cli::Format::Human | cli::Format::Table => {
+                output::print_series(url, &result);
+            },
+        }
+    }
+
+    reports
+}
+
+/// Check that no volume of the series at `url` is missing.
+fn check_series(
+    client: &bedetheque::Client,
+    url: &Url,
+    volumes: &BTreeSet<u8>,
+) -> Result<Vec<error::Error>> {
+    Ok(client
+        .series_volumes(url)
+        .context("failed to get volumes from bedetheque")?
+        .difference(volumes)
+        .map(|&volume| error::Error::MissingVolume(volume))
+        .collect())
+}
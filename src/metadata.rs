@@ -1,17 +1,24 @@
 //! Extract book's metadata from the book's page.
 
+use anyhow::{bail, Result};
 use kuchiki::traits::*;
 use once_cell::sync::Lazy;
 use regex::Regex;
+use serde::{Deserialize, Serialize};
 use std::{collections::BTreeSet, iter::FromIterator};
+use url::Url;
+
+/// CSS selector for the information block of an album page.
+static INFOS_SELECTOR: Lazy<kuchiki::Selectors> =
+    Lazy::new(|| kuchiki::Selectors::compile(".infos").expect("invalid infos selector"));
 
 /// CSS selector for the information fields.
 static INFO_SELECTOR: Lazy<kuchiki::Selectors> =
     Lazy::new(|| kuchiki::Selectors::compile(".infos li").expect("invalid info selector"));
 
-/// Regex to extract the writer or pencillers name.
-static AUTHOR_REGEX: Lazy<Regex> = Lazy::new(|| {
-    Regex::new(r#"(?P<category>Scénario|Dessin) :\s+(?P<name>[^,]+)"#).expect("valid author regexp")
+/// CSS selector for the link to the series page.
+static SERIES_SELECTOR: Lazy<kuchiki::Selectors> = Lazy::new(|| {
+    kuchiki::Selectors::compile("a[href*='/serie-']").expect("invalid series selector")
 });
 
 /// Regex to extract the publication year.
@@ -19,24 +26,89 @@ static YEAR_REGEX: Lazy<Regex> = Lazy::new(|| {
     Regex::new(r#"Dépot légal :\s+[0-9]{2}/(?P<year>[0-9]{4})"#).expect("valid year regexp")
 });
 
+/// Regex to extract the volume number.
+static VOLUME_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"Tome :\s+(?P<volume>[0-9]+)"#).expect("valid volume regexp"));
+
+/// Labels of the information fields listing the authors, by role.
+#[derive(Debug, Clone)]
+pub(crate) struct AuthorLabels {
+    /// Labels of the pencillers, the other ones are the writers'.
+    pencillers: Vec<String>,
+    /// Regex to extract the writer or pencillers name.
+    regex: Regex,
+}
+
+impl AuthorLabels {
+    /// Initialize the labels of the `writers` and the `pencillers` fields.
+    pub(crate) fn new(writers: &[String], pencillers: &[String]) -> Self {
+        let labels = writers
+            .iter()
+            .chain(pencillers)
+            .map(|label| regex::escape(label))
+            .collect::<Vec<_>>()
+            .join("|");
+        let regex = Regex::new(&format!(r#"(?P<category>{labels})\s*:\s+(?P<name>[^,]+)"#))
+            .expect("valid author regexp");
+
+        Self {
+            pencillers: pencillers.to_vec(),
+            regex,
+        }
+    }
+}
+
+impl Default for AuthorLabels {
+    fn default() -> Self {
+        Self::new(&["Scénario".to_owned()], &["Dessin".to_owned()])
+    }
+}
+
 /// Volume metadata.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) struct VolumeInfo {
     /// Authors names.
     pub(crate) authors: String,
+    /// Authors names, one by one: as some contain hyphens, they can't be
+    /// reliably split from `authors`.
+    #[serde(default)]
+    pub(crate) names: Vec<String>,
     /// Publicaton year of every editions.
     pub(crate) years: BTreeSet<u16>,
+    /// URL of the series page, if found.
+    pub(crate) series: Option<Url>,
+    /// Volume number, if any.
+    #[serde(default)]
+    pub(crate) volume: Option<u8>,
+    /// Title of the series, if found.
+    #[serde(default)]
+    pub(crate) series_title: Option<String>,
 }
 
 impl VolumeInfo {
-    pub(crate) fn new(page: &kuchiki::NodeRef) -> Self {
+    /// Extract the metadata from an album page, the authors being found
+    /// according to `labels`.
+    ///
+    /// Fails if `page` isn't an album page (e.g. a redirection to a series
+    /// page), rather than returning empty metadata.
+    pub(crate) fn new(page: &kuchiki::NodeRef, labels: &AuthorLabels) -> Result<Self> {
+        if INFOS_SELECTOR
+            .filter(page.descendants().elements())
+            .next()
+            .is_none()
+        {
+            bail!("not an album page");
+        }
+
         let mut years = BTreeSet::new();
         let mut writers = BTreeSet::new();
         let mut pencillers = BTreeSet::new();
+        let mut volume = None;
 
         for node in INFO_SELECTOR.filter(page.descendants().elements()) {
             let content = node.text_contents();
 
-            if let Some(captures) = AUTHOR_REGEX.captures(&content) {
+            if let Some(captures) = labels.regex.captures(&content) {
                 let category = captures
                     .name("category")
                     .expect("invalid capture group for author's category")
@@ -48,7 +120,7 @@ impl VolumeInfo {
                     .trim()
                     .to_owned();
 
-                if category == "Dessin" {
+                if labels.pencillers.iter().any(|label| label == category) {
                     // Don't add the author as penciller if they are already
                     // registered as a writer.
                     //
@@ -68,6 +140,11 @@ impl VolumeInfo {
                     .parse::<u16>()
                     .expect("valid year");
                 years.insert(year);
+            } else if let Some(captures) = VOLUME_REGEX.captures(&content) {
+                // Out of range numbers can't match a file name anyway.
+                volume = captures
+                    .name("volume")
+                    .and_then(|m| m.as_str().parse::<u8>().ok());
             }
         }
 
@@ -76,9 +153,25 @@ impl VolumeInfo {
         let mut authors = Vec::from_iter(writers);
         authors.extend(pencillers);
 
-        Self {
+        let (series, series_title) = SERIES_SELECTOR
+            .filter(page.descendants().elements())
+            .find_map(|node| {
+                let attributes = node.attributes.borrow();
+                let url = attributes
+                    .get("href")
+                    .and_then(|link| Url::parse(link).ok())?;
+                let title = node.text_contents().trim().to_owned();
+                Some((url, (!title.is_empty()).then_some(title)))
+            })
+            .map_or((None, None), |(url, title)| (Some(url), title));
+
+        Ok(Self {
             authors: authors.join("-"),
+            names: authors,
             years,
-        }
+            series,
+            volume,
+            series_title,
+        })
     }
 }
@@ -0,0 +1,58 @@
+//! Overall outcome of a run, reported as exit code.
+
+use crate::{bedetheque, cbz::Report};
+use anyhow::{Error, Result};
+use std::process::ExitCode;
+
+/// Outcome of a check, from the least to the most severe.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Ord, PartialOrd)]
+pub(crate) enum Outcome {
+    /// Everything is fine.
+    #[default]
+    Ok = 0,
+    /// Content errors (width, date, authors, year...).
+    Invalid = 1,
+    /// Couldn't check (archive or I/O error).
+    Unchecked = 2,
+    /// Couldn't reach bedetheque.
+    Network = 3,
+}
+
+impl Outcome {
+    /// Classify an error that prevented a check.
+    pub(crate) fn of_error(err: &Error) -> Self {
+        if bedetheque::is_network_error(err) || bedetheque::is_budget_exhausted(err) {
+            Self::Network
+        } else {
+            Self::Unchecked
+        }
+    }
+
+    /// Classify an error that prevented loading a book: unless bedetheque is
+    /// unreachable, the file name is invalid.
+    pub(crate) fn of_skip(err: &Error) -> Self {
+        match Self::of_error(err) {
+            Self::Network => Self::Network,
+            _ => Self::Invalid,
+        }
+    }
+
+    /// Classify the result of a book's check.
+    pub(crate) fn of_result(result: &Result<Report>) -> Self {
+        match result {
+            Ok(report) if report.errors.is_empty() => Self::Ok,
+            Ok(_) => Self::Invalid,
+            Err(err) => Self::of_error(err),
+        }
+    }
+
+    /// Record another outcome, the most severe one is kept.
+    pub(crate) fn record(&mut self, other: Self) {
+        *self = (*self).max(other);
+    }
+
+    /// Return the process exit code for this outcome.
+    pub(crate) fn exit_code(self) -> ExitCode {
+        ExitCode::from(self as u8)
+    }
+}
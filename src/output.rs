@@ -0,0 +1,429 @@
+//! Report the results of the checks.
+
+use crate::{
+    bedetheque::{self, Stats},
+    cbz::{Book, Report, Timings},
+    cli::Options,
+    error::Error,
+    termio,
+    warning::{SkipReason, Warning},
+};
+use anyhow::{Context, Result};
+use serde_json::{json, Value};
+use std::{
+    collections::BTreeMap,
+    io::{self, Write},
+    path::Path,
+};
+use terminal_size::Width;
+use url::Url;
+
+/// Print the result of a book's check in a human-readable way.
+pub(crate) fn print_human(book: &Book, result: &Result<Report>, opts: &Options) {
+    if let Some(template) = &opts.template {
+        println!("{}", template.render(book, result));
+        return;
+    }
+
+    match result {
+        Ok(report) => {
+            // No error? Great!
+            if report.errors.is_empty() {
+                termio::print_ok(book.file_name());
+                for warning in &report.warnings {
+                    println!("--> {warning}");
+                }
+            } else {
+                // Report every error detected.
+                termio::print_err(book.file_name());
+                if let Some(url) = book.ref_url() {
+                    match book.language() {
+                        Some(language) => println!("Checked against {} ({language})", url.as_str()),
+                        None => println!("Checked against {}", url.as_str()),
+                    }
+                }
+                for err in &report.errors {
+                    println!("==> {err}");
+                    if opts.explain {
+                        println!("    {}", book.explain(err));
+                    }
+                }
+                for warning in &report.warnings {
+                    println!("--> {warning}");
+                }
+            }
+            if opts.timings {
+                print_timings(&report.timings);
+            }
+        }
+        Err(err) if bedetheque::is_budget_exhausted(err) => {
+            termio::print_warn(&format!(
+                "{}: skipped (request budget exhausted)",
+                book.file_name()
+            ));
+        }
+        Err(err) => {
+            // Failed to even check the book, inform the user.
+            termio::print_err(&format!("failed to check {}: {err:?}", book.file_name()));
+        }
+    }
+    println!();
+}
+
+/// Print the warnings that aren't tied to a book's check.
+pub(crate) fn print_warnings(warnings: &[Warning]) {
+    for warning in warnings {
+        termio::print_warn(&warning.to_string());
+    }
+}
+
+/// Print the number of files skipped for each reason, if any.
+pub(crate) fn print_skipped(warnings: &[Warning]) {
+    let counts = skipped_counts(warnings);
+    if counts.is_empty() {
+        return;
+    }
+
+    let total = counts.values().sum::<usize>();
+    let details = counts
+        .iter()
+        .map(|(reason, count)| format!("{count} {}", reason.as_str()))
+        .collect::<Vec<_>>()
+        .join(", ");
+    termio::print_warn(&format!("skipped {total} files: {details}"));
+}
+
+/// Convert the skipped files into JSON: the reason of each one, and the
+/// number of files skipped for each reason.
+pub(crate) fn skipped_to_json(warnings: &[Warning]) -> Value {
+    let files = warnings
+        .iter()
+        .filter_map(|warning| match warning {
+            Warning::Skipped(path, reason, message) => Some(json!({
+                "path": path.display().to_string(),
+                "reason": reason.as_str(),
+                "message": message,
+            })),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let counts = skipped_counts(warnings)
+        .into_iter()
+        .map(|(reason, count)| (reason.as_str().to_owned(), Value::from(count)))
+        .collect::<serde_json::Map<_, _>>();
+
+    json!({
+        "files": files,
+        "counts": counts,
+    })
+}
+
+/// Count the files skipped for each reason.
+fn skipped_counts(warnings: &[Warning]) -> BTreeMap<SkipReason, usize> {
+    let mut counts = BTreeMap::new();
+    for warning in warnings {
+        if let Warning::Skipped(_, reason, _) = warning {
+            *counts.entry(*reason).or_insert(0) += 1;
+        }
+    }
+    counts
+}
+
+/// Convert the result of a book's check into JSON.
+pub(crate) fn to_json(book: &Book, result: &Result<Report>, timings: bool) -> Value {
+    match result {
+        Ok(report) => {
+            let errors = report
+                .errors
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>();
+            let mut value = json!({
+                "file": book.file_name(),
+                "url": book.ref_url().map(Url::as_str),
+                "language": book.language(),
+                "ok": errors.is_empty(),
+                "errors": errors,
+                "warnings": report
+                    .warnings
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>(),
+            });
+            if timings {
+                value["timings"] = timings_to_json(&report.timings);
+            }
+            value
+        }
+        Err(err) => json!({
+            "file": book.file_name(),
+            "url": book.ref_url().map(Url::as_str),
+            "ok": false,
+            "skipped": bedetheque::is_budget_exhausted(err),
+            "failure": format!("{err:#}"),
+        }),
+    }
+}
+
+/// Print a JSON value on a single line, right away.
+pub(crate) fn print_ndjson(value: &Value) {
+    let mut stdout = io::stdout().lock();
+
+    writeln!(&mut stdout, "{value}").expect("write JSON line");
+    // Consumers rely on getting each line as soon as it's printed.
+    stdout.flush().expect("flush JSON line");
+}
+
+/// Widest file name in a table, longer ones are truncated.
+const TABLE_NAME_WIDTH: usize = 60;
+
+/// A CSV row: file name, ok/failed, reference URL and errors.
+pub(crate) type CsvRow = [String; 4];
+
+/// Convert the result of a book's check into a CSV row.
+pub(crate) fn to_csv(book: &Book, result: &Result<Report>) -> CsvRow {
+    let (status, errors) = summarize(result);
+
+    [
+        book.file_name().to_owned(),
+        status.to_owned(),
+        book.ref_url()
+            .map(Url::as_str)
+            .unwrap_or_default()
+            .to_owned(),
+        errors,
+    ]
+}
+
+/// Summarize the result of a book's check: its status (`ok`, `failed` or
+/// `skipped`) and its errors, `;`-separated.
+pub(crate) fn summarize(result: &Result<Report>) -> (&'static str, String) {
+    match result {
+        Ok(report) => (
+            if report.errors.is_empty() {
+                "ok"
+            } else {
+                "failed"
+            },
+            report
+                .errors
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join("; "),
+        ),
+        Err(err) if bedetheque::is_budget_exhausted(err) => {
+            ("skipped", "request budget exhausted".to_owned())
+        }
+        Err(err) => ("failed", format!("failed to check: {err:#}")),
+    }
+}
+
+/// Print the CSV rows, with a header.
+pub(crate) fn print_csv(rows: &[CsvRow]) -> Result<()> {
+    let mut writer = csv::Writer::from_writer(io::stdout());
+
+    writer
+        .write_record(["file", "status", "url", "errors"])
+        .context("failed to write CSV header")?;
+    for row in rows {
+        writer
+            .write_record(row)
+            .context("failed to write CSV row")?;
+    }
+
+    writer.flush().context("failed to write CSV")
+}
+
+/// Print the CSV rows as an aligned table: status, file name and errors.
+///
+/// Long file names are truncated, and so are the errors that would overflow
+/// the terminal.
+pub(crate) fn print_table(rows: &[CsvRow]) {
+    let name_width = rows
+        .iter()
+        .map(|[file, ..]| file.chars().count())
+        .max()
+        .unwrap_or_default()
+        .clamp("FILE".len(), TABLE_NAME_WIDTH);
+    // The longest status is `skipped`, and columns are two spaces apart.
+    let errors_width = terminal_size::terminal_size()
+        .map(|(Width(width), _)| usize::from(width).saturating_sub(7 + 2 + name_width + 2));
+
+    println!("{:<7}  {:<name_width$}  ERRORS", "STATUS", "FILE");
+    for [file, status, _, errors] in rows {
+        let file = truncate(file, name_width);
+        let errors = errors_width.map_or_else(|| errors.clone(), |width| truncate(errors, width));
+        let line = format!("{status:<7}  {file:<name_width$}  {errors}");
+        println!("{}", line.trim_end());
+    }
+}
+
+/// Truncate `text` to `width` characters, the cut being marked by an ellipsis.
+fn truncate(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        return text.to_owned();
+    }
+
+    let mut truncated = text
+        .chars()
+        .take(width.saturating_sub(1))
+        .collect::<String>();
+    truncated.push('…');
+    truncated
+}
+
+/// Print the results of the books of a directory, followed by a tally.
+pub(crate) fn print_directory(dir: &Path, results: &[(Book, Result<Report>)], opts: &Options) {
+    println!("=== {} ===", dir.display());
+    println!();
+    for (book, result) in results {
+        print_human(book, result, opts);
+    }
+
+    let passed = count_passed(results);
+    let msg = format!(
+        "{}: {passed} passed, {} failed",
+        dir.display(),
+        results.len() - passed
+    );
+    if passed == results.len() {
+        termio::print_ok(&msg);
+    } else {
+        termio::print_err(&msg);
+    }
+    println!();
+}
+
+/// Convert the results of the books of a directory into JSON.
+pub(crate) fn directory_to_json(results: &[(Book, Result<Report>)], timings: bool) -> Value {
+    let passed = count_passed(results);
+
+    json!({
+        "books": results
+            .iter()
+            .map(|(book, result)| to_json(book, result, timings))
+            .collect::<Vec<_>>(),
+        "passed": passed,
+        "failed": results.len() - passed,
+    })
+}
+
+/// Count the books that passed every check.
+fn count_passed(results: &[(Book, Result<Report>)]) -> usize {
+    results
+        .iter()
+        .filter(|(_, result)| matches!(result, Ok(report) if report.errors.is_empty()))
+        .count()
+}
+
+/// Print the result of a series' completeness check.
+pub(crate) fn print_series(url: &Url, result: &Result<Vec<Error>>) {
+    match result {
+        Ok(errors) if errors.is_empty() => {
+            termio::print_ok(&format!("series {}", url.as_str()));
+        }
+        Ok(errors) => {
+            termio::print_err(&format!("series {}", url.as_str()));
+            for err in errors {
+                println!("==> {err}");
+            }
+        }
+        Err(err) => {
+            termio::print_err(&format!("failed to check series {}: {err:?}", url.as_str()));
+        }
+    }
+    println!();
+}
+
+/// Convert the result of a series' completeness check into JSON.
+pub(crate) fn series_to_json(url: &Url, result: &Result<Vec<Error>>) -> Value {
+    match result {
+        Ok(errors) => json!({
+            "series": url.as_str(),
+            "ok": errors.is_empty(),
+            "errors": errors.iter().map(ToString::to_string).collect::<Vec<_>>(),
+        }),
+        Err(err) => json!({
+            "series": url.as_str(),
+            "ok": false,
+            "failure": format!("{err:#}"),
+        }),
+    }
+}
+
+/// Bedetheque page matched by a book.
+pub(crate) struct Match {
+    /// File name of the book.
+    pub(crate) file: String,
+    /// URL of the matched album, if any.
+    pub(crate) url: Option<Url>,
+    /// Title of the matched series, if known.
+    pub(crate) series: Option<String>,
+}
+
+/// Print the bedetheque page matched by each book, as a table.
+pub(crate) fn print_matches(matches: &[Match]) {
+    println!("Matches:");
+    for m in matches {
+        println!(
+            "  {} -> {} ({})",
+            m.file,
+            m.url.as_ref().map_or("not found", Url::as_str),
+            m.series.as_deref().unwrap_or("unknown series")
+        );
+    }
+    println!();
+}
+
+/// Convert the bedetheque page matched by each book into JSON.
+pub(crate) fn matches_to_json(matches: &[Match]) -> Value {
+    matches
+        .iter()
+        .map(|m| {
+            json!({
+                "file": m.file,
+                "url": m.url.as_ref().map(Url::as_str),
+                "series": m.series,
+            })
+        })
+        .collect()
+}
+
+/// Print the network usage statistics.
+pub(crate) fn print_stats(stats: &Stats) {
+    println!(
+        "Network: {} requests to bedetheque, {} KiB received",
+        stats.requests,
+        stats.bytes / 1024
+    );
+}
+
+/// Convert the network usage statistics into JSON.
+pub(crate) fn stats_to_json(stats: &Stats) -> Value {
+    json!({
+        "requests": stats.requests,
+        "bytes": stats.bytes,
+    })
+}
+
+/// Print the time spent in each step of the check.
+fn print_timings(timings: &Timings) {
+    println!(
+        "Timings: find_book {:.3}s, fetch_info {:.3}s, images {:.3}s, total {:.3}s",
+        timings.find_book.as_secs_f64(),
+        timings.fetch_info.as_secs_f64(),
+        timings.images.as_secs_f64(),
+        timings.total.as_secs_f64(),
+    );
+}
+
+/// Convert the time spent in each step of the check into JSON (in seconds).
+fn timings_to_json(timings: &Timings) -> Value {
+    json!({
+        "find_book": timings.find_book.as_secs_f64(),
+        "fetch_info": timings.fetch_info.as_secs_f64(),
+        "images": timings.images.as_secs_f64(),
+        "total": timings.total.as_secs_f64(),
+    })
+}
@@ -0,0 +1,199 @@
+//! Inspection of the pictures (i.e. pages) of a book.
+
+use crate::jpeg;
+use anyhow::{Context, Result};
+use image::{
+    codecs::{gif::GifDecoder, jpeg::JpegDecoder, png::PngDecoder, webp::WebPDecoder},
+    ImageDecoder, ImageFormat,
+};
+use imagesize::ImageType;
+use std::io::{BufReader, Cursor};
+
+/// What is known about a picture, from its bytes alone.
+pub struct Picture {
+    /// Image format, if detected.
+    pub format: Option<ImageType>,
+    /// Bits per pixel, if detected.
+    pub bit_depth: Option<u16>,
+    /// Width, in pixels.
+    pub width: usize,
+    /// Height, in pixels.
+    pub height: usize,
+    /// EXIF metadata, if any.
+    pub exif: Option<exif::Exif>,
+    /// Format that required the fallback decoder to get the dimensions, if
+    /// any.
+    pub fallback: Option<ImageFormat>,
+    /// Whether the picture is animated (multi-frame GIF, or APNG).
+    pub animated: bool,
+}
+
+/// Inspect the picture in `bytes`: format, dimensions and EXIF metadata.
+///
+/// Only the headers are read, the picture isn't decoded.
+pub fn inspect(bytes: &[u8]) -> Result<Picture> {
+    // `imagesize` doesn't support every format, `image` may know better.
+    let (width, height, fallback) = match imagesize::blob_size(bytes) {
+        Ok(size) => (size.width, size.height, None),
+        Err(err) => {
+            let (format, (width, height)) =
+                fallback_size(bytes).with_context(|| format!("cannot get width: {err}"))?;
+            (width as usize, height as usize, Some(format))
+        }
+    };
+
+    let mut reader = BufReader::new(Cursor::new(bytes));
+    let exif = match exif::Reader::new().read_from_container(&mut reader) {
+        Ok(exif) => Some(exif),
+        Err(exif::Error::NotFound(_)) => None,
+        Err(err) => return Err(err).context("cannot check EXIF"),
+    };
+
+    let format = imagesize::image_type(bytes).ok();
+
+    Ok(Picture {
+        format,
+        bit_depth: format.and_then(|format| bit_depth(bytes, format)),
+        width,
+        height,
+        exif,
+        fallback,
+        animated: format.is_some_and(|format| is_animated(bytes, format)),
+    })
+}
+
+/// Return the format and the dimensions of the picture, using the decoders
+/// of `image`.
+fn fallback_size(bytes: &[u8]) -> Result<(ImageFormat, (u32, u32))> {
+    let reader = image::io::Reader::new(Cursor::new(bytes))
+        .with_guessed_format()
+        .context("cannot guess format")?;
+    let format = reader.format().context("unknown format")?;
+    let dimensions = reader.into_dimensions().context("cannot decode header")?;
+
+    Ok((format, dimensions))
+}
+
+/// Return the bits per pixel of the picture, from its header.
+fn bit_depth(bytes: &[u8], format: ImageType) -> Option<u16> {
+    // Decoders only read the header on creation.
+    let reader = Cursor::new(bytes);
+    let color = match format {
+        ImageType::Jpeg => JpegDecoder::new(reader).ok()?.color_type(),
+        ImageType::Png => PngDecoder::new(reader).ok()?.color_type(),
+        ImageType::Gif => GifDecoder::new(reader).ok()?.color_type(),
+        ImageType::Webp => WebPDecoder::new(reader).ok()?.color_type(),
+        _ => return None,
+    };
+
+    Some(color.bits_per_pixel())
+}
+
+/// Check if the picture has several frames, from its blocks (GIF) or chunks
+/// (PNG) headers.
+fn is_animated(bytes: &[u8], format: ImageType) -> bool {
+    match format {
+        ImageType::Gif => gif_frames(bytes).is_some_and(|frames| frames > 1),
+        ImageType::Png => is_apng(bytes),
+        _ => false,
+    }
+}
+
+/// Return the number of frames of a GIF image, counted until the trailer (or
+/// the second frame, that's enough), if well-formed.
+fn gif_frames(bytes: &[u8]) -> Option<usize> {
+    // Header, then the logical screen descriptor.
+    let mut pos = 13;
+    let mut frames = 0;
+    pos += color_table_len(*bytes.get(10)?);
+
+    while frames < 2 {
+        match bytes.get(pos)? {
+            // Extension: introducer, label then sub-blocks.
+            0x21 => pos = skip_sub_blocks(bytes, pos + 2)?,
+            // Image descriptor, then the LZW code size and the sub-blocks.
+            0x2C => {
+                frames += 1;
+                pos += 10 + color_table_len(*bytes.get(pos + 9)?);
+                pos = skip_sub_blocks(bytes, pos + 1)?;
+            }
+            // Trailer.
+            0x3B => break,
+            _ => return None,
+        }
+    }
+
+    Some(frames)
+}
+
+/// Return the length of the color table announced by the packed `flags` of a
+/// GIF descriptor.
+fn color_table_len(flags: u8) -> usize {
+    if flags & 0x80 == 0 {
+        return 0;
+    }
+    3 << ((flags & 0x07) + 1)
+}
+
+/// Return the position following the GIF sub-blocks starting at `pos`.
+fn skip_sub_blocks(bytes: &[u8], mut pos: usize) -> Option<usize> {
+    loop {
+        let len = usize::from(*bytes.get(pos)?);
+        pos += 1 + len;
+        if len == 0 {
+            return Some(pos);
+        }
+    }
+}
+
+/// Check if a PNG image is an APNG, i.e. has an `acTL` chunk before its data.
+fn is_apng(bytes: &[u8]) -> bool {
+    // Chunks follow the signature.
+    let mut pos = 8;
+
+    while let (Some(len), Some(kind)) = (bytes.get(pos..pos + 4), bytes.get(pos + 4..pos + 8)) {
+        match kind {
+            b"acTL" => return true,
+            b"IDAT" => return false,
+            _ => {}
+        }
+        let len = u32::from_be_bytes([len[0], len[1], len[2], len[3]]) as usize;
+        // Length, type, data then CRC.
+        let Some(next) = pos.checked_add(12 + len) else {
+            return false;
+        };
+        pos = next;
+    }
+
+    false
+}
+
+/// Return the number of bytes following the image data (after the JPEG End Of
+/// Image marker, or the PNG `IEND` chunk), if the end of the data is found.
+pub(crate) fn trailing_bytes(bytes: &[u8], format: ImageType) -> Option<usize> {
+    let len = match format {
+        ImageType::Jpeg => jpeg::image_len(bytes)?,
+        ImageType::Png => png_len(bytes)?,
+        _ => return None,
+    };
+
+    Some(bytes.len() - len)
+}
+
+/// Return the length of a PNG image, up to and including its `IEND` chunk, if
+/// found.
+fn png_len(bytes: &[u8]) -> Option<usize> {
+    // Chunks follow the signature.
+    let mut pos = 8;
+
+    loop {
+        let len = bytes.get(pos..pos + 4)?;
+        let len = u32::from_be_bytes([len[0], len[1], len[2], len[3]]) as usize;
+        let kind = bytes.get(pos + 4..pos + 8)?;
+        // Length, type, data then CRC.
+        pos = pos.checked_add(12 + len)?;
+        if kind == b"IEND" {
+            return (pos <= bytes.len()).then_some(pos);
+        }
+    }
+}
@@ -0,0 +1,30 @@
+//! Preview of the normalization of titles and authors, to understand why a
+//! lookup or an authors check behaves as it does.
+
+use crate::{authors, bedetheque, filename};
+
+/// Print how `text` is normalized, as a title and as an authors list.
+///
+/// If `text` is a book's file name, its title and authors are previewed
+/// instead.
+pub(crate) fn preview(text: &str, aliases: &authors::Aliases) {
+    let (title, authors) = match filename::parse_filename(text) {
+        Ok(parsed) => (parsed.title, parsed.authors),
+        Err(_) => (text.to_owned(), text.to_owned()),
+    };
+
+    println!("Title: {title}");
+    println!(
+        "  searched as: {}",
+        bedetheque::search_forms(&title).join(" | ")
+    );
+    println!(
+        "  matched as: {}",
+        bedetheque::match_forms(&title).join(" | ")
+    );
+    println!("Authors: {authors}");
+    println!(
+        "  compared as: {}",
+        authors::normalize(&authors, &[], aliases).join(" | ")
+    );
+}
@@ -0,0 +1,121 @@
+//! Sources of books' metadata.
+
+use crate::{
+    bedetheque::Client, cache, catalog::Catalog, cli::Options, metadata::VolumeInfo,
+    series::SeriesConfig, snapshot::Snapshot, warning::Warning,
+};
+use anyhow::Result;
+use std::fmt;
+use url::Url;
+
+/// The source has no metadata for the book, which is then checked offline.
+#[derive(Debug)]
+pub(crate) struct NoMetadata;
+
+impl fmt::Display for NoMetadata {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "no metadata")
+    }
+}
+
+impl std::error::Error for NoMetadata {}
+
+/// What is known about a book, to locate it in a metadata source.
+pub(crate) struct Lookup<'a> {
+    /// File name of the book.
+    pub(crate) file_name: &'a str,
+    /// Title, from the file name.
+    pub(crate) title: &'a str,
+    /// Volume number, from the file name.
+    pub(crate) volume: Option<u8>,
+    /// Configuration of the book's series, if any.
+    pub(crate) series: Option<&'a SeriesConfig>,
+}
+
+/// A source of books' metadata.
+pub(crate) trait MetadataProvider {
+    /// Locate the book, and return the URL of its metadata.
+    ///
+    /// Fails with `NoMetadata` if the book is known not to be listed, rather
+    /// than not found.
+    fn locate(&self, book: &Lookup<'_>) -> Result<Url>;
+
+    /// Return the metadata at `url`.
+    fn metadata(&self, url: &Url) -> Result<VolumeInfo>;
+
+    /// Return the language (code) in which the book at `url` was found, if
+    /// known.
+    fn language(&self, _url: &Url) -> Option<&'static str> {
+        None
+    }
+
+    /// Return the minor issues met while locating the book at `url`, if any.
+    fn warnings(&self, _url: &Url) -> Vec<Warning> {
+        Vec::new()
+    }
+}
+
+/// Sources of metadata selected by the options.
+pub(crate) struct Sources {
+    snapshot: Option<Snapshot>,
+    catalog: Option<Catalog>,
+    client: Option<Client>,
+}
+
+impl Sources {
+    /// Load the metadata sources selected by `opts`: a snapshot, a catalog,
+    /// or bedetheque unless offline.
+    pub(crate) fn load(opts: &Options) -> Result<Self> {
+        Ok(Self {
+            snapshot: opts
+                .metadata_from
+                .as_deref()
+                .map(Snapshot::load)
+                .transpose()?,
+            catalog: opts
+                .metadata_csv
+                .as_deref()
+                .map(Catalog::load)
+                .transpose()?,
+            client: new_client(opts)?,
+        })
+    }
+
+    /// Return the source the metadata come from, if any.
+    pub(crate) fn provider(&self) -> Option<&dyn MetadataProvider> {
+        match (&self.snapshot, &self.catalog, &self.client) {
+            (Some(snapshot), _, _) => Some(snapshot),
+            (None, Some(catalog), _) => Some(catalog),
+            (None, None, Some(client)) => Some(client),
+            (None, None, None) => None,
+        }
+    }
+
+    /// Return the bedetheque client, unless offline.
+    pub(crate) fn client(&self) -> Option<&Client> {
+        self.client.as_ref()
+    }
+}
+
+/// Setup the bedetheque client, unless we're offline.
+///
+/// The client starts with the lookups from the persistent cache, if any.
+fn new_client(opts: &Options) -> Result<Option<Client>> {
+    if opts.offline || opts.local_metadata() {
+        return Ok(None);
+    }
+
+    let client = Client::new(
+        opts.tape.clone(),
+        opts.languages.clone(),
+        opts.search_field,
+        opts.max_requests,
+        opts.reuse_cache,
+        opts.author_labels.clone(),
+    );
+    if let (true, Some(path)) = (opts.reuse_cache, &opts.cache) {
+        client.preload(cache::load(path)?);
+    }
+
+    Ok(Some(client))
+}
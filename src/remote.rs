@@ -0,0 +1,128 @@
+//! Books given as HTTP(S) URLs, downloaded to be checked like local files.
+
+use crate::termio;
+use anyhow::{Context, Result};
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+    process,
+};
+use url::Url;
+
+/// Books downloaded in a temporary directory, removed when dropped.
+pub(crate) struct Downloads {
+    agent: ureq::Agent,
+    /// Temporary directory, one sub-directory per download, once created.
+    dir: Option<PathBuf>,
+    /// Number of downloads so far.
+    count: usize,
+}
+
+impl Downloads {
+    /// Initialize the downloads, sent through `agent`.
+    pub(crate) fn new(agent: ureq::Agent) -> Self {
+        Self {
+            agent,
+            dir: None,
+            count: 0,
+        }
+    }
+
+    /// Download the book at `url`, and return its local path.
+    ///
+    /// The book keeps the name of the URL's last path segment, so that it's
+    /// reported (and parsed) under that name.
+    pub(crate) fn fetch(&mut self, url: &Url) -> Result<PathBuf> {
+        let name = url
+            .path_segments()
+            .and_then(Iterator::last)
+            .map(percent_decode)
+            .filter(|name| !name.is_empty())
+            .with_context(|| format!("no file name in {}", url.as_str()))?;
+        self.count += 1;
+        let count = self.count;
+        let dir = self.dir()?.join(count.to_string());
+        fs::create_dir(&dir)
+            .with_context(|| format!("cannot create directory {}", dir.display()))?;
+        let path = dir.join(name);
+
+        let response = self
+            .agent
+            .request_url("GET", url)
+            .call()
+            .with_context(|| format!("failed to download {}", url.as_str()))?;
+        let mut file =
+            fs::File::create(&path).with_context(|| format!("cannot create {}", path.display()))?;
+        // Streamed, archives can be large.
+        io::copy(&mut response.into_reader(), &mut file)
+            .with_context(|| format!("failed to download {}", url.as_str()))?;
+
+        Ok(path)
+    }
+
+    /// Return the temporary directory, created on first use.
+    fn dir(&mut self) -> Result<&Path> {
+        if self.dir.is_none() {
+            let dir = env::temp_dir().join(format!("cbzlint-{}", process::id()));
+            // Created exclusively: an existing one (or a symlink) could be
+            // anybody's.
+            fs::create_dir(&dir)
+                .with_context(|| format!("cannot create directory {}", dir.display()))?;
+            self.dir = Some(dir);
+        }
+
+        Ok(self.dir.as_deref().expect("created above"))
+    }
+}
+
+impl Drop for Downloads {
+    fn drop(&mut self) {
+        // Nothing was downloaded if it wasn't created.
+        let Some(dir) = &self.dir else {
+            return;
+        };
+        if let Err(err) = fs::remove_dir_all(dir) {
+            termio::print_warn(&format!(
+                "cannot remove downloads in {}: {err}",
+                dir.display()
+            ));
+        }
+    }
+}
+
+/// Return the URL if `path` is an HTTP(S) URL rather than a local path.
+pub(crate) fn as_url(path: &Path) -> Result<Option<Url>> {
+    let Some(path) = path
+        .to_str()
+        .filter(|path| path.starts_with("http://") || path.starts_with("https://"))
+    else {
+        return Ok(None);
+    };
+
+    Url::parse(path)
+        .map(Some)
+        .with_context(|| format!("invalid URL {path}"))
+}
+
+/// Decode the percent-encoded bytes of a URL's path segment.
+fn percent_decode(segment: &str) -> String {
+    let bytes = segment.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let byte = segment
+            .get(i + 1..i + 3)
+            .filter(|_| bytes[i] == b'%')
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        if let Some(byte) = byte {
+            decoded.push(byte);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
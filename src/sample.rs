@@ -0,0 +1,65 @@
+//! Deterministic sampling of the pages to check.
+
+use std::collections::BTreeSet;
+
+/// Select the indexes of the entries to check, among `len`: the first `n`,
+/// the last `n` and `n` interior ones picked at random.
+///
+/// The same `seed` always gives the same selection.
+pub(crate) fn select(len: usize, n: usize, seed: u64) -> BTreeSet<usize> {
+    // Any sample size is accepted, but there's no more than `len` to pick.
+    let n = n.min(len);
+    // Small archive, nothing to skip.
+    if len <= n.saturating_mul(3) {
+        return (0..len).collect();
+    }
+
+    let mut selected = (0..n).chain(len - n..len).collect::<BTreeSet<_>>();
+    // Partial Fisher-Yates shuffle of the interior.
+    let mut interior = (n..len - n).collect::<Vec<_>>();
+    let mut rng = SplitMix64(seed);
+    for i in 0..n {
+        #[allow(clippy::cast_possible_truncation)] // Bounded by the length.
+        let j = i + (rng.next() % (interior.len() - i) as u64) as usize;
+        interior.swap(i, j);
+        selected.insert(interior[i]);
+    }
+
+    selected
+}
+
+/// `SplitMix64` pseudo-random generator: tiny, and good enough for sampling.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    /// Return the next pseudo-random number.
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn select_huge_sample() {
+        let all = (0..10).collect::<BTreeSet<_>>();
+
+        assert_eq!(select(10, usize::MAX, 0), all);
+        assert_eq!(select(10, usize::MAX / 3 + 1, 0), all);
+    }
+
+    #[test]
+    fn select_deterministic() {
+        let selected = select(100, 5, 42);
+
+        assert_eq!(selected.len(), 15);
+        assert!((0..5).chain(95..100).all(|i| selected.contains(&i)));
+        assert_eq!(selected, select(100, 5, 42));
+    }
+}
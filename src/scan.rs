@@ -0,0 +1,278 @@
+//! Discovery of the books to check.
+
+use crate::{
+    archive,
+    cbz::{self, Book},
+    cli::Options,
+    ignore::{IgnoreList, IGNORE_FILE},
+    journal::Journal,
+    outcome::Outcome,
+    provider::MetadataProvider,
+    series::SeriesConfig,
+    warning::{SkipReason, Warning},
+};
+use anyhow::{Context, Result};
+use std::{
+    ffi::OsStr,
+    fs,
+    path::{Path, PathBuf},
+};
+use zip::ZipArchive;
+
+/// A book found on disk, not loaded yet (i.e. not located on bedetheque).
+pub(crate) struct Candidate {
+    /// Path of the book, under its collection archive's path if any.
+    path: PathBuf,
+    /// Configuration of the book's series, if any.
+    series: Option<SeriesConfig>,
+    /// Collection archive containing the book, and the book's entry in it, if
+    /// any.
+    container: Option<(PathBuf, String)>,
+}
+
+impl Candidate {
+    /// Return the path of the book.
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Load the book: parse its file name, and locate it with `provider`.
+    pub(crate) fn load(
+        &self,
+        provider: Option<&dyn MetadataProvider>,
+        opts: &Options,
+    ) -> Result<Book> {
+        let book = Book::new(
+            provider,
+            &self.path,
+            self.series.as_ref(),
+            opts.underscores_as_spaces,
+        )?;
+
+        Ok(match &self.container {
+            Some((container, entry)) => book.in_container(container, entry),
+            None => book,
+        })
+    }
+}
+
+/// Get every CBZ file under `path`, loaded.
+///
+/// Books that can't be loaded are skipped, and recorded in `outcome`. Every
+/// skipped file is reported in `warnings`.
+pub(crate) fn get_books(
+    provider: Option<&dyn MetadataProvider>,
+    path: &Path,
+    opts: &Options,
+    journal: Option<&Journal>,
+    outcome: &mut Outcome,
+    warnings: &mut Vec<Warning>,
+) -> Result<Vec<Book>> {
+    let candidates = find_books(path, opts, journal, warnings)?;
+
+    Ok(candidates
+        .iter()
+        .filter_map(|candidate| match candidate.load(provider, opts) {
+            Ok(book) => Some(book),
+            Err(err) => {
+                outcome.record(Outcome::of_skip(&err));
+                warnings.push(Warning::not_loaded(candidate.path.clone(), &err));
+                None
+            }
+        })
+        .collect())
+}
+
+/// Find every CBZ file under `path`, without loading them.
+///
+/// If `path` is a CBZ instead of a directory, it's returned directly. So is a
+/// directory of images in loose mode, as an unpacked book (such directories
+/// are also found while scanning).
+///
+/// Collection archives (ZIP of CBZ) are opened, and the books inside are
+/// returned.
+///
+/// Sub-directories are only explored in recursive mode.
+///
+/// Books that passed in the previous run, per the `journal`, are skipped.
+///
+/// Every skipped file is reported in `warnings`.
+pub(crate) fn find_books(
+    path: &Path,
+    opts: &Options,
+    journal: Option<&Journal>,
+    warnings: &mut Vec<Warning>,
+) -> Result<Vec<Candidate>> {
+    // Case 1. `path` is a file, or an unpacked book.
+    if !path.is_dir() || (opts.loose && is_unpacked_book(path)?) {
+        if journal.is_some_and(|journal| journal.has_passed(path)) {
+            warnings.push(Warning::skipped(path.to_owned(), SkipReason::Resumed));
+            return Ok(vec![]);
+        }
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        let series = SeriesConfig::load(dir)?;
+        if path.extension() == Some(OsStr::new("zip")) {
+            return find_collection_books(path, series.as_ref(), warnings);
+        }
+        return Ok(vec![Candidate {
+            path: path.to_owned(),
+            series,
+            container: None,
+        }]);
+    }
+    // Case 2. `path` is a directory.
+    let scanner = Scanner {
+        root: path,
+        ignore_list: IgnoreList::load(path)?,
+        opts,
+        journal,
+    };
+    let mut books = Vec::new();
+    scanner.scan(path, &mut books, warnings)?;
+
+    Ok(books)
+}
+
+/// Check if the directory `path` is an unpacked book: it contains images, and
+/// no books.
+fn is_unpacked_book(path: &Path) -> Result<bool> {
+    let entries =
+        fs::read_dir(path).with_context(|| format!("failed to read dir {}", path.display()))?;
+    let mut has_images = false;
+
+    for entry in entries {
+        let entry =
+            entry.with_context(|| format!("cannot access entry under {}", path.display()))?;
+        let entry = entry.path();
+        if archive::is_book(&entry) || entry.extension() == Some(OsStr::new("zip")) {
+            return Ok(false);
+        }
+        has_images |= entry.is_file() && cbz::is_image(&entry.to_string_lossy());
+    }
+
+    Ok(has_images)
+}
+
+/// Find every CBZ inside the collection archive `path`.
+///
+/// Entries that aren't books are skipped, and reported in `warnings`.
+fn find_collection_books(
+    path: &Path,
+    series: Option<&SeriesConfig>,
+    warnings: &mut Vec<Warning>,
+) -> Result<Vec<Candidate>> {
+    let file = fs::File::open(path).with_context(|| format!("cannot open {}", path.display()))?;
+    let collection =
+        ZipArchive::new(file).with_context(|| format!("cannot read {}", path.display()))?;
+    // Entries aren't listed in a stable order.
+    let mut names = collection
+        .file_names()
+        .filter(|name| !name.ends_with('/'))
+        .collect::<Vec<_>>();
+    names.sort_unstable();
+    let mut books = Vec::new();
+
+    for name in names {
+        // Books are looked up by their name, as if they were extracted.
+        let book_path = path.join(name);
+        if !archive::is_book(&book_path) {
+            warnings.push(Warning::skipped(book_path, SkipReason::NotCbz));
+            continue;
+        }
+        books.push(Candidate {
+            path: book_path,
+            series: series.cloned(),
+            container: Some((path.to_owned(), name.to_owned())),
+        });
+    }
+
+    Ok(books)
+}
+
+/// Directory explorer.
+struct Scanner<'a> {
+    /// Directory being checked.
+    root: &'a Path,
+    /// Books to ignore, relative to `root`.
+    ignore_list: IgnoreList,
+    /// Explore sub-directories, parse the file names...
+    opts: &'a Options,
+    /// Books that passed in the previous run, if resuming.
+    journal: Option<&'a Journal>,
+}
+
+impl Scanner<'_> {
+    /// Collect the books in `dir`.
+    fn scan(
+        &self,
+        dir: &Path,
+        books: &mut Vec<Candidate>,
+        warnings: &mut Vec<Warning>,
+    ) -> Result<()> {
+        let series = SeriesConfig::load(dir)?;
+        let entries =
+            fs::read_dir(dir).with_context(|| format!("failed to read dir {}", dir.display()))?;
+
+        for entry in entries {
+            let entry =
+                entry.with_context(|| format!("cannot access entry under {}", dir.display()))?;
+            // Symlinks aren't followed, to avoid looping (e.g. `sub/loop -> ..`).
+            let is_dir = entry
+                .file_type()
+                .with_context(|| format!("cannot access {}", entry.path().display()))?
+                .is_dir();
+            let path = entry.path();
+            let relpath = path.strip_prefix(self.root).unwrap_or(&path);
+
+            if self.ignore_list.is_ignored(relpath) {
+                warnings.push(Warning::Skipped(
+                    path,
+                    SkipReason::Ignored,
+                    format!("ignored by {IGNORE_FILE}"),
+                ));
+                continue;
+            }
+            // In loose mode, a directory of images is a book, not explored.
+            let is_unpacked = is_dir && self.opts.loose && is_unpacked_book(&path)?;
+            if self.opts.recursive && is_dir && !is_unpacked {
+                self.scan(&path, books, warnings)?;
+                continue;
+            }
+            if (!is_dir || is_unpacked) && !is_selected(&path, self.opts) {
+                // Filtered out on request, not worth a warning.
+                continue;
+            }
+
+            if path.extension() == Some(OsStr::new("zip")) {
+                books.extend(find_collection_books(&path, series.as_ref(), warnings)?);
+                continue;
+            }
+            if !is_unpacked && !archive::is_book(&path) {
+                // Not a book, nothing wrong with that.
+                warnings.push(Warning::skipped(path, SkipReason::NotCbz));
+                continue;
+            }
+            if self
+                .journal
+                .is_some_and(|journal| journal.has_passed(&path))
+            {
+                warnings.push(Warning::skipped(path, SkipReason::Resumed));
+                continue;
+            }
+            books.push(Candidate {
+                path,
+                series: series.clone(),
+                container: None,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// Check if the file at `path` passes the `--only` filter, if any.
+pub(crate) fn is_selected(path: &Path, opts: &Options) -> bool {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+
+    opts.only.is_empty() || opts.only.iter().any(|pattern| pattern.matches(&name))
+}
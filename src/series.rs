@@ -0,0 +1,56 @@
+//! Per-directory series configuration.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::{fs, io, path::Path};
+use url::Url;
+
+/// Name of the per-directory series configuration file.
+pub(crate) const SERIES_FILE: &str = "series.toml";
+
+/// Series configuration file, as written by the user.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawConfig {
+    url: Option<String>,
+    title: Option<String>,
+    #[serde(default)]
+    widths: Vec<usize>,
+}
+
+/// Series configuration, shared by every book of a directory.
+#[derive(Debug, Clone)]
+pub(crate) struct SeriesConfig {
+    /// URL of the series page on bedetheque.
+    pub(crate) url: Option<Url>,
+    /// Title to use when searching the series on bedetheque.
+    pub(crate) title: Option<String>,
+    /// Widths accepted in addition to the one from the file name.
+    pub(crate) widths: Vec<usize>,
+}
+
+impl SeriesConfig {
+    /// Load the series configuration from the directory `dir`, if any.
+    pub(crate) fn load(dir: &Path) -> Result<Option<Self>> {
+        let path = dir.join(SERIES_FILE);
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => {
+                return Err(err).with_context(|| format!("failed to read {}", path.display()))
+            }
+        };
+        let raw = toml::from_str::<RawConfig>(&content)
+            .with_context(|| format!("invalid series config {}", path.display()))?;
+        let url = raw
+            .url
+            .map(|url| Url::parse(&url).with_context(|| format!("invalid series URL `{url}`")))
+            .transpose()?;
+
+        Ok(Some(Self {
+            url,
+            title: raw.title,
+            widths: raw.widths,
+        }))
+    }
+}
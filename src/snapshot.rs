@@ -0,0 +1,60 @@
+//! Metadata snapshots, to check books without network access.
+
+use crate::{
+    metadata::VolumeInfo,
+    provider::{Lookup, MetadataProvider},
+};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fs, path::Path};
+use url::Url;
+
+/// Snapshot of the books' metadata.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct Snapshot {
+    /// URL of each book, by file name.
+    books: BTreeMap<String, Url>,
+    /// Metadata of each book, by URL.
+    metadata: BTreeMap<Url, VolumeInfo>,
+}
+
+impl Snapshot {
+    /// Load a snapshot from `path`.
+    pub(crate) fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("failed to read snapshot {}", path.display()))?;
+
+        serde_json::from_str(&content)
+            .with_context(|| format!("invalid snapshot {}", path.display()))
+    }
+
+    /// Save the snapshot to `path`.
+    pub(crate) fn save(&self, path: &Path) -> Result<()> {
+        let content = serde_json::to_string_pretty(self).context("failed to serialize snapshot")?;
+
+        fs::write(path, content)
+            .with_context(|| format!("failed to write snapshot {}", path.display()))
+    }
+
+    /// Record the URL and metadata of a book.
+    pub(crate) fn record(&mut self, file_name: &str, url: &Url, info: VolumeInfo) {
+        self.books.insert(file_name.to_owned(), url.clone());
+        self.metadata.insert(url.clone(), info);
+    }
+}
+
+impl MetadataProvider for Snapshot {
+    fn locate(&self, book: &Lookup<'_>) -> Result<Url> {
+        self.books
+            .get(book.file_name)
+            .cloned()
+            .context("book not found in snapshot")
+    }
+
+    fn metadata(&self, url: &Url) -> Result<VolumeInfo> {
+        self.metadata
+            .get(url)
+            .cloned()
+            .with_context(|| format!("no metadata in snapshot for {}", url.as_str()))
+    }
+}
@@ -0,0 +1,93 @@
+//! Ordering of the reported books.
+
+use crate::cbz::{Book, Report};
+use anyhow::{bail, Error, Result};
+use std::{cmp::Ordering, str::FromStr};
+
+/// Order in which the books are reported.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub(crate) enum Order {
+    /// Natural order of the file names (e.g. `T2` before `T10`).
+    #[default]
+    Name,
+    /// Failures first, then by name.
+    Status,
+    /// Order in which the books were found.
+    None,
+}
+
+impl FromStr for Order {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "name" => Self::Name,
+            "status" => Self::Status,
+            "none" => Self::None,
+            _ => bail!("expected `name`, `status` or `none`"),
+        })
+    }
+}
+
+/// Sort the results of the books' check in the requested order.
+pub(crate) fn sort_results(results: &mut [(Book, Result<Report>)], order: Order) {
+    match order {
+        Order::Name => results.sort_by(|(a, _), (b, _)| natural_cmp(a.file_name(), b.file_name())),
+        Order::Status => results.sort_by(|(a, a_result), (b, b_result)| {
+            passed(b_result)
+                .cmp(&passed(a_result))
+                .then_with(|| natural_cmp(a.file_name(), b.file_name()))
+        }),
+        Order::None => (),
+    }
+}
+
+/// Check if a book passed every check.
+fn passed(result: &Result<Report>) -> bool {
+    matches!(result, Ok(report) if report.errors.is_empty())
+}
+
+/// Compare two strings in natural order: digit runs are compared by value.
+fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut a = a.chars().peekable();
+    let mut b = b.chars().peekable();
+
+    loop {
+        match (a.peek().copied(), b.peek().copied()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let x = take_number(&mut a);
+                let y = take_number(&mut b);
+                // Compare by value (ignoring leading zeros), then by length.
+                let ordering = x
+                    .trim_start_matches('0')
+                    .len()
+                    .cmp(&y.trim_start_matches('0').len())
+                    .then_with(|| x.trim_start_matches('0').cmp(y.trim_start_matches('0')))
+                    .then_with(|| x.len().cmp(&y.len()));
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            (Some(x), Some(y)) => {
+                if x != y {
+                    return x.cmp(&y);
+                }
+                a.next();
+                b.next();
+            }
+        }
+    }
+}
+
+/// Consume and return the leading run of ASCII digits.
+fn take_number(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) -> String {
+    let mut number = String::new();
+    while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+        number.push(digit);
+    }
+
+    number
+}
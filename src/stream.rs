@@ -0,0 +1,138 @@
+//! Lazy checks of the books, for the library's consumers.
+
+use crate::{
+    cbz::{Book, Report},
+    cli::Options,
+    output,
+    provider::Sources,
+    scan::{self, Candidate},
+    warning::Warning,
+};
+use anyhow::Result;
+use serde::Serialize;
+use std::{collections::VecDeque, path::PathBuf, vec};
+use url::Url;
+
+/// Result of a book's check.
+#[derive(Debug, Clone, Serialize)]
+pub struct BookReport {
+    /// File name of the book.
+    pub file: String,
+    /// Path of the book.
+    pub path: PathBuf,
+    /// Status of the check: `ok`, `failed` or `skipped`.
+    pub status: &'static str,
+    /// URL of the metadata the book was checked against, if any.
+    pub url: Option<Url>,
+    /// Errors found, or why the book couldn't be checked.
+    pub errors: Vec<String>,
+    /// Minor issues found, that don't fail the check.
+    pub warnings: Vec<String>,
+}
+
+impl BookReport {
+    fn new(book: &Book, result: &Result<Report>) -> Self {
+        let (status, failure) = output::summarize(result);
+        let (errors, warnings) = match result {
+            Ok(report) => (
+                report.errors.iter().map(ToString::to_string).collect(),
+                report.warnings.iter().map(ToString::to_string).collect(),
+            ),
+            Err(_) => (vec![failure], Vec::new()),
+        };
+
+        Self {
+            file: book.file_name().to_owned(),
+            path: book.path().to_owned(),
+            status,
+            url: book.ref_url().cloned(),
+            errors,
+            warnings,
+        }
+    }
+
+    /// Report a file skipped with `message` (e.g. not a book, or not found on
+    /// bedetheque).
+    fn skipped(path: PathBuf, message: String) -> Self {
+        Self {
+            file: path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned(),
+            path,
+            status: "skipped",
+            url: None,
+            errors: vec![message],
+            warnings: Vec::new(),
+        }
+    }
+}
+
+/// Checks of the books, one book per iteration: results can be processed as
+/// soon as each book is checked, and the checks stopped at any time.
+///
+/// The books of a directory are all found before the first one is checked,
+/// but each one is only located on bedetheque when its turn comes. The skipped
+/// files are reported too, with the `skipped` status.
+pub struct Checks {
+    opts: Options,
+    sources: Sources,
+    /// Files or directories left to explore.
+    paths: vec::IntoIter<PathBuf>,
+    /// Books found, not loaded yet.
+    books: VecDeque<Candidate>,
+    /// Files skipped while exploring, not reported yet.
+    skipped: VecDeque<Warning>,
+}
+
+impl Iterator for Checks {
+    type Item = Result<BookReport>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(warning) = self.skipped.pop_front() {
+                if let Warning::Skipped(path, _, message) = warning {
+                    return Some(Ok(BookReport::skipped(path, message)));
+                }
+                continue;
+            }
+            if let Some(candidate) = self.books.pop_front() {
+                let provider = self.sources.provider();
+                let report = match candidate.load(provider, &self.opts) {
+                    Ok(book) => BookReport::new(&book, &book.check(provider, &self.opts)),
+                    Err(err) => {
+                        BookReport::skipped(candidate.path().to_owned(), format!("{err:#}"))
+                    }
+                };
+                return Some(Ok(report));
+            }
+
+            let path = self.paths.next()?;
+            let mut warnings = Vec::new();
+            match scan::find_books(&path, &self.opts, None, &mut warnings) {
+                Ok(books) => self.books.extend(books),
+                Err(err) => return Some(Err(err)),
+            }
+            self.skipped.extend(warnings);
+        }
+    }
+}
+
+/// Check the books, with the options from `args` (as given on the command
+/// line, without the binary name).
+///
+/// Nothing is checked until the returned iterator is consumed.
+pub fn check(args: impl IntoIterator<Item = String>) -> Result<Checks> {
+    let mut opts = Options::parse_from(args)?;
+    let sources = Sources::load(&opts)?;
+    let paths = std::mem::take(&mut opts.paths).into_iter();
+
+    Ok(Checks {
+        opts,
+        sources,
+        paths,
+        books: VecDeque::new(),
+        skipped: VecDeque::new(),
+    })
+}
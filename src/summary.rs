@@ -0,0 +1,108 @@
+//! Aggregate statistics of a run, for dashboards.
+
+use crate::{
+    bedetheque::Stats,
+    cbz::Report,
+    error::Error,
+    output,
+    warning::{SkipReason, Warning},
+};
+use anyhow::{Context, Result};
+use serde_json::json;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::Path,
+    time::Instant,
+};
+
+/// Counters accumulated while checking the books.
+pub(crate) struct Summary {
+    /// Start of the run.
+    started: Instant,
+    /// Books that passed every check.
+    passed: usize,
+    /// Books with errors, or that failed to load (e.g. not found).
+    failed: usize,
+    /// Books that couldn't be checked (e.g. bedetheque unreachable).
+    not_checked: usize,
+    /// Number of failed books, by check reporting an error.
+    errors: BTreeMap<&'static str, usize>,
+    /// Number of skipped files, by reason.
+    skipped: BTreeMap<&'static str, usize>,
+}
+
+impl Summary {
+    /// Initialize the summary, the run starting now.
+    pub(crate) fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            passed: 0,
+            failed: 0,
+            not_checked: 0,
+            errors: BTreeMap::new(),
+            skipped: BTreeMap::new(),
+        }
+    }
+
+    /// Record the files skipped before being checked, from the `warnings`.
+    ///
+    /// Books that failed to load count as failed, as for the exit code, unless
+    /// bedetheque was unreachable.
+    pub(crate) fn record_skipped(&mut self, warnings: &[Warning]) {
+        for warning in warnings {
+            let Warning::Skipped(_, reason, _) = warning else {
+                continue;
+            };
+            match reason {
+                SkipReason::UnparseableName | SkipReason::Unreadable | SkipReason::NotFound => {
+                    self.failed += 1;
+                }
+                SkipReason::Network => self.not_checked += 1,
+                SkipReason::NotCbz | SkipReason::Ignored | SkipReason::Resumed => (),
+            }
+            *self.skipped.entry(reason.as_str()).or_default() += 1;
+        }
+    }
+
+    /// Record the result of a book's check.
+    pub(crate) fn record(&mut self, result: &Result<Report>) {
+        match result {
+            Ok(report) if report.errors.is_empty() => self.passed += 1,
+            Ok(report) => {
+                self.failed += 1;
+                // A book counts once per check, however many errors.
+                let checks = report
+                    .errors
+                    .iter()
+                    .map(Error::check)
+                    .collect::<BTreeSet<_>>();
+                for check in checks {
+                    *self.errors.entry(check).or_default() += 1;
+                }
+            }
+            Err(_) => self.not_checked += 1,
+        }
+    }
+
+    /// Save the summary as JSON to `path`, with the network usage statistics
+    /// (if any).
+    pub(crate) fn save(&self, path: &Path, stats: Option<Stats>) -> Result<()> {
+        let mut json = json!({
+            "checked": self.passed + self.failed,
+            "passed": self.passed,
+            "failed": self.failed,
+            "not_checked": self.not_checked,
+            "errors": self.errors,
+            "skipped": self.skipped,
+            "elapsed": self.started.elapsed().as_secs_f64(),
+        });
+        if let Some(stats) = stats {
+            json["network"] = output::stats_to_json(&stats);
+        }
+        let content = serde_json::to_string_pretty(&json).context("failed to serialize summary")?;
+
+        fs::write(path, content)
+            .with_context(|| format!("failed to write summary {}", path.display()))
+    }
+}
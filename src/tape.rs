@@ -0,0 +1,52 @@
+//! Record and replay of the bedetheque responses.
+
+use anyhow::{Context, Result};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use url::Url;
+
+/// Where the HTTP responses are recorded to, or replayed from.
+#[derive(Debug, Clone)]
+pub(crate) enum Tape {
+    /// Save every response in the directory.
+    Record(PathBuf),
+    /// Serve the responses from the directory, without network access.
+    Replay(PathBuf),
+}
+
+/// Save the response `html` for `url` in the directory `dir`.
+pub(crate) fn save(dir: &Path, url: &Url, html: &str) -> Result<()> {
+    fs::create_dir_all(dir).with_context(|| format!("failed to create {}", dir.display()))?;
+    let path = response_path(dir, url);
+
+    fs::write(&path, html).with_context(|| format!("failed to write {}", path.display()))
+}
+
+/// Load the response for `url` from the directory `dir`.
+pub(crate) fn load(dir: &Path, url: &Url) -> Result<String> {
+    let path = response_path(dir, url);
+
+    fs::read_to_string(&path).with_context(|| {
+        format!(
+            "no recorded response for {} ({})",
+            url.as_str(),
+            path.display()
+        )
+    })
+}
+
+/// Path of the response for `url` in the directory `dir`.
+///
+/// URLs are hashed (FNV-1a, stable across runs) to get valid file names.
+fn response_path(dir: &Path, url: &Url) -> PathBuf {
+    let hash = url
+        .as_str()
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        });
+
+    dir.join(format!("{hash:016x}.html"))
+}
@@ -0,0 +1,99 @@
+//! Templates of the per-book line, for the human-readable output.
+
+use crate::{
+    cbz::{Book, Report},
+    output,
+};
+use anyhow::{bail, Error, Result};
+use std::str::FromStr;
+use url::Url;
+
+/// A placeholder of a template.
+#[derive(Debug, Clone, Copy)]
+enum Placeholder {
+    /// `ok`, `failed` or `skipped`.
+    Status,
+    /// File name of the book.
+    File,
+    /// URL checked against, if any.
+    Url,
+    /// Errors, `;`-separated.
+    Errors,
+    /// Number of errors.
+    Count,
+}
+
+/// A part of a template.
+#[derive(Debug, Clone)]
+enum Part {
+    Text(String),
+    Placeholder(Placeholder),
+}
+
+/// Template of the line reported for each book, e.g. `{status} {file}`.
+#[derive(Debug, Clone)]
+pub(crate) struct Template(Vec<Part>);
+
+impl FromStr for Template {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = Vec::new();
+        let mut rest = s;
+
+        while let Some(start) = rest.find('{') {
+            let Some(len) = rest[start..].find('}') else {
+                bail!("unclosed placeholder in `{s}`");
+            };
+            if start > 0 {
+                parts.push(Part::Text(rest[..start].to_owned()));
+            }
+            parts.push(Part::Placeholder(match &rest[start + 1..start + len] {
+                "status" => Placeholder::Status,
+                "file" => Placeholder::File,
+                "url" => Placeholder::Url,
+                "errors" => Placeholder::Errors,
+                "count" => Placeholder::Count,
+                name => bail!(
+                    "unknown placeholder `{{{name}}}`, expected one of `{{status}}`, `{{file}}`, \
+                     `{{url}}`, `{{errors}}` or `{{count}}`",
+                    name = name
+                ),
+            }));
+            rest = &rest[start + len + 1..];
+        }
+        if !rest.is_empty() {
+            parts.push(Part::Text(rest.to_owned()));
+        }
+
+        Ok(Self(parts))
+    }
+}
+
+impl Template {
+    /// Render the template for the result of a book's check.
+    pub(crate) fn render(&self, book: &Book, result: &Result<Report>) -> String {
+        let (status, errors) = output::summarize(result);
+        let count = match result {
+            Ok(report) => report.errors.len(),
+            // Failing to check counts as an error.
+            Err(_) => 1,
+        };
+
+        self.0
+            .iter()
+            .map(|part| match part {
+                Part::Text(text) => text.clone(),
+                Part::Placeholder(Placeholder::Status) => status.to_owned(),
+                Part::Placeholder(Placeholder::File) => book.file_name().to_owned(),
+                Part::Placeholder(Placeholder::Url) => book
+                    .ref_url()
+                    .map(Url::as_str)
+                    .unwrap_or_default()
+                    .to_owned(),
+                Part::Placeholder(Placeholder::Errors) => errors.clone(),
+                Part::Placeholder(Placeholder::Count) => count.to_string(),
+            })
+            .collect()
+    }
+}
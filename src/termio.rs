@@ -16,15 +16,17 @@ pub(crate) fn print_ok(msg: &str) {
 }
 
 /// Print a warning message, in yellow.
+///
+/// Warnings go to stderr to keep stdout clean for machine-readable outputs.
 pub(crate) fn print_warn(msg: &str) {
-    let mut stdout = StandardStream::stdout(ColorChoice::Auto);
+    let mut stderr = StandardStream::stderr(ColorChoice::Auto);
 
-    stdout
+    stderr
         .set_color(ColorSpec::new().set_fg(Some(Color::Yellow)))
         .expect("set color");
-    writeln!(&mut stdout, "WARN  {msg}").expect("write message");
+    writeln!(&mut stderr, "WARN  {msg}").expect("write message");
 
-    stdout.reset().expect("reset color");
+    stderr.reset().expect("reset color");
 }
 
 /// Print an error message, in yellow.
@@ -0,0 +1,137 @@
+use crate::{bedetheque, error::Error};
+use std::{fmt, path::PathBuf};
+use url::Url;
+
+pub(crate) enum Warning {
+    Skipped(PathBuf, SkipReason, String),
+    DuplicateUrl(String, String, Url),
+    AmbiguousMatch(String, usize),
+    PrefixMatch(String),
+    Punctuation(String, String),
+    NoYear,
+    NoMetadata,
+    Minor(Error),
+    Sampled(usize, usize),
+    Fallback(String, usize),
+}
+
+impl Warning {
+    /// Warn about the file at `path`, skipped for `reason`.
+    pub(crate) fn skipped(path: PathBuf, reason: SkipReason) -> Self {
+        Self::Skipped(path, reason, reason.to_string())
+    }
+
+    /// Warn about the book at `path`, skipped as it failed to load with `err`.
+    pub(crate) fn not_loaded(path: PathBuf, err: &anyhow::Error) -> Self {
+        Self::Skipped(path, SkipReason::of(err), format!("{err:#}"))
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Skipped(path, _, message) => {
+                write!(f, "skip {}: {message}", path.display())
+            }
+            Self::DuplicateUrl(a, b, url) => {
+                write!(f, "{a} and {b} both resolve to {}", url.as_str())
+            }
+            Self::AmbiguousMatch(title, count) => {
+                write!(
+                    f,
+                    "ambiguous match, {count} books named `{title}` on bedetheque"
+                )
+            }
+            Self::PrefixMatch(title) => {
+                write!(
+                    f,
+                    "no series named exactly `{title}` on bedetheque, matched by prefix"
+                )
+            }
+            Self::Punctuation(title, chars) => {
+                write!(
+                    f,
+                    "title `{title}` contains punctuation ({chars}) that may break the lookup"
+                )
+            }
+            Self::NoYear => {
+                write!(f, "no publication year on bedetheque, year not checked")
+            }
+            Self::NoMetadata => {
+                write!(f, "no metadata, authors and year not checked")
+            }
+            Self::Minor(err) => err.fmt(f),
+            Self::Sampled(checked, total) => {
+                write!(
+                    f,
+                    "only {checked} pages out of {total} were checked (sampling)"
+                )
+            }
+            Self::Fallback(format, count) => {
+                write!(f, "{count} {format} pages required the fallback decoder")
+            }
+        }
+    }
+}
+
+/// Why a file was skipped.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd)]
+pub(crate) enum SkipReason {
+    /// Not a book (CBZ or CBT), nothing wrong with that.
+    NotCbz,
+    /// The file name doesn't follow the naming convention.
+    UnparseableName,
+    /// The file name can't be read (e.g. not UTF-8).
+    Unreadable,
+    /// The book can't be found on bedetheque.
+    NotFound,
+    /// Bedetheque can't be reached (or the request budget is exhausted).
+    Network,
+    /// Ignored by an ignore file.
+    Ignored,
+    /// Already passed, when resuming.
+    Resumed,
+}
+
+impl SkipReason {
+    /// Classify an error that prevented loading a book: unless bedetheque is
+    /// unreachable, it couldn't be found.
+    pub(crate) fn of(err: &anyhow::Error) -> Self {
+        if let Some(&reason) = err.downcast_ref::<Self>() {
+            return reason;
+        }
+
+        if bedetheque::is_network_error(err) || bedetheque::is_budget_exhausted(err) {
+            Self::Network
+        } else {
+            Self::NotFound
+        }
+    }
+
+    /// Return the machine-readable name of the reason.
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Self::NotCbz => "not_cbz",
+            Self::UnparseableName => "unparseable_name",
+            Self::Unreadable => "unreadable",
+            Self::NotFound => "not_found",
+            Self::Network => "network",
+            Self::Ignored => "ignored",
+            Self::Resumed => "resumed",
+        }
+    }
+}
+
+impl fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotCbz => write!(f, "not a CBZ or CBT"),
+            Self::UnparseableName => write!(f, "unparseable file name"),
+            Self::Unreadable => write!(f, "unreadable file name"),
+            Self::NotFound => write!(f, "book not found"),
+            Self::Network => write!(f, "bedetheque unreachable"),
+            Self::Ignored => write!(f, "ignored"),
+            Self::Resumed => write!(f, "already passed (--resume)"),
+        }
+    }
+}
@@ -0,0 +1,139 @@
+//! Live checks of the books, as they change.
+
+use crate::{
+    archive,
+    cli::{Format, Options},
+    ignore::IgnoreList,
+    outcome::Outcome,
+    output,
+    provider::MetadataProvider,
+    scan, termio,
+};
+use anyhow::{bail, Context, Result};
+use notify::{Event, RecursiveMode, Watcher};
+use std::{
+    collections::BTreeSet,
+    fs,
+    path::{Path, PathBuf},
+    sync::mpsc,
+    time::Duration,
+};
+
+/// How long to wait for the events to settle before checking.
+const DEBOUNCE_DELAY: Duration = Duration::from_millis(500);
+
+/// A watched directory.
+struct Root {
+    /// Canonical path of the directory.
+    path: PathBuf,
+    /// Books to ignore, relative to `path`.
+    ignore_list: IgnoreList,
+}
+
+/// Watch the directories to check, and check every book created or modified
+/// in them.
+///
+/// Books are filtered like when scanning the directories: ignored books and
+/// the ones not matching `--only` aren't checked.
+///
+/// Never returns, unless an error occurs.
+pub(crate) fn watch(provider: Option<&dyn MetadataProvider>, opts: &Options) -> Result<()> {
+    if opts.format == Format::Csv {
+        bail!("CSV output is not supported by --watch");
+    }
+    if opts.format == Format::Table {
+        bail!("table output is not supported by --watch");
+    }
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx).context("cannot start watcher")?;
+    let mode = if opts.recursive {
+        RecursiveMode::Recursive
+    } else {
+        RecursiveMode::NonRecursive
+    };
+    let mut roots = Vec::new();
+    for path in &opts.paths {
+        watcher
+            .watch(path, mode)
+            .with_context(|| format!("cannot watch {}", path.display()))?;
+        if path.is_dir() {
+            roots.push(Root {
+                path: fs::canonicalize(path)
+                    .with_context(|| format!("cannot access {}", path.display()))?,
+                ignore_list: IgnoreList::load(path)?,
+            });
+        }
+    }
+
+    loop {
+        let mut changed = BTreeSet::new();
+        collect_books(rx.recv().context("watcher stopped")?, &mut changed);
+        // Archives are written in several steps, wait until it's done.
+        while let Ok(event) = rx.recv_timeout(DEBOUNCE_DELAY) {
+            collect_books(event, &mut changed);
+        }
+
+        for path in changed
+            .into_iter()
+            .filter(|path| path.is_file() && is_selected(path, &roots, opts))
+        {
+            // Nothing to do with the outcome, there's no exit code.
+            let mut warnings = Vec::new();
+            let books = match scan::get_books(
+                provider,
+                &path,
+                opts,
+                None,
+                &mut Outcome::default(),
+                &mut warnings,
+            ) {
+                Ok(books) => books,
+                // Removed, or still being written: it'll come back if needed.
+                Err(err) => {
+                    termio::print_warn(&format!("cannot check {}: {err:#}", path.display()));
+                    continue;
+                }
+            };
+            output::print_warnings(&warnings);
+            for book in books {
+                let result = book.check(provider, opts);
+
+                if matches!(opts.format, Format::Json | Format::Ndjson) {
+                    output::print_ndjson(&output::to_json(&book, &result, opts.timings));
+                } else {
+                    output::print_human(&book, &result, opts);
+                }
+            }
+        }
+    }
+}
+
+/// Check if the book at `path` is to be checked: not ignored in its watched
+/// directory, and passing the `--only` filter.
+fn is_selected(path: &Path, roots: &[Root], opts: &Options) -> bool {
+    // Events are reported under the watched path, which may be relative.
+    let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_owned());
+    let is_ignored = roots.iter().any(|root| {
+        path.strip_prefix(&root.path)
+            .is_ok_and(|relpath| root.ignore_list.is_ignored(relpath))
+    });
+
+    !is_ignored && scan::is_selected(&path, opts)
+}
+
+/// Collect the books created or modified by `event`.
+fn collect_books(event: notify::Result<Event>, books: &mut BTreeSet<PathBuf>) {
+    match event {
+        Ok(event) if event.kind.is_create() || event.kind.is_modify() => {
+            books.extend(
+                event
+                    .paths
+                    .into_iter()
+                    .filter(|path| archive::is_book(path)),
+            );
+        }
+        Ok(_) => (),
+        Err(err) => termio::print_warn(&format!("watch error: {err}")),
+    }
+}